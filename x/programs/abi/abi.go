@@ -0,0 +1,194 @@
+// Copyright (C) 2023, Ava Labs, Inc. All rights reserved.
+// See the file LICENSE for licensing terms.
+
+// Package abi describes the host functions a program may import. The manifest
+// in abi.json is shared with the Rust SDK, which generates its imports from it,
+// so the runtime and programs agree on module names, function names and
+// signatures.
+package abi
+
+import (
+	"context"
+	_ "embed"
+	"encoding/json"
+	"errors"
+	"fmt"
+
+	"github.com/tetratelabs/wazero/api"
+)
+
+var (
+	//go:embed abi.json
+	manifestBytes []byte
+
+	manifest = mustParse(manifestBytes)
+
+	ErrUnknownModule     = errors.New("unknown host module")
+	ErrUnknownFunction   = errors.New("unknown host function")
+	ErrMissingFunction   = errors.New("missing host function")
+	ErrSignatureMismatch = errors.New("host function signature mismatch")
+	ErrVersionMismatch   = errors.New("abi version mismatch")
+)
+
+// VersionFnName is the function programs built with the SDK export, returning
+// the version of the manifest they were built against.
+const VersionFnName = "abi_version"
+
+// Manifest lists every host module and the functions it exports.
+type Manifest struct {
+	// Version is bumped whenever a module or function is changed in a way
+	// that breaks programs built against an older manifest.
+	Version uint32   `json:"version"`
+	Modules []Module `json:"modules"`
+}
+
+type Module struct {
+	Name      string     `json:"name"`
+	Functions []Function `json:"functions"`
+}
+
+type Function struct {
+	Name    string   `json:"name"`
+	Params  []Param  `json:"params"`
+	Results []string `json:"results"`
+}
+
+// Param is a named function parameter. Type is one of i32, i64, u32, u64, ptr
+// or len. Pointers and lengths are 32 bit values in the guest's memory.
+type Param struct {
+	Name string `json:"name"`
+	Type string `json:"type"`
+}
+
+func mustParse(b []byte) *Manifest {
+	m := new(Manifest)
+	if err := json.Unmarshal(b, m); err != nil {
+		panic(fmt.Sprintf("failed to parse abi manifest: %v", err))
+	}
+	return m
+}
+
+// Version returns the version of the manifest.
+func Version() uint32 {
+	return manifest.Version
+}
+
+// Lookup returns the module named [name] from the manifest.
+func Lookup(name string) (*Module, bool) {
+	for i := range manifest.Modules {
+		if manifest.Modules[i].Name == name {
+			return &manifest.Modules[i], true
+		}
+	}
+	return nil, false
+}
+
+func (m *Module) function(name string) (*Function, bool) {
+	for i := range m.Functions {
+		if m.Functions[i].Name == name {
+			return &m.Functions[i], true
+		}
+	}
+	return nil, false
+}
+
+// CheckHostModule returns an error unless [mod] exports exactly the functions
+// listed for the module [name], with matching signatures.
+func CheckHostModule(name string, mod api.Module) error {
+	m, ok := Lookup(name)
+	if !ok {
+		return fmt.Errorf("%w: %s", ErrUnknownModule, name)
+	}
+
+	defs := mod.ExportedFunctionDefinitions()
+	for _, fn := range m.Functions {
+		def, ok := defs[fn.Name]
+		if !ok {
+			return fmt.Errorf("%w: %s.%s", ErrMissingFunction, name, fn.Name)
+		}
+		if err := fn.check(name, def); err != nil {
+			return err
+		}
+	}
+	for exported := range defs {
+		if _, ok := m.function(exported); !ok {
+			return fmt.Errorf("%w: %s.%s", ErrUnknownFunction, name, exported)
+		}
+	}
+	return nil
+}
+
+// CheckImports returns an error if a program imports a function from a host
+// module in the manifest that the manifest does not list, or imports it with a
+// different signature. Imports from other modules, e.g. WASI, are ignored.
+func CheckImports(imports []api.FunctionDefinition) error {
+	for _, def := range imports {
+		moduleName, name, _ := def.Import()
+		m, ok := Lookup(moduleName)
+		if !ok {
+			continue
+		}
+		fn, ok := m.function(name)
+		if !ok {
+			return fmt.Errorf("%w: %s.%s", ErrUnknownFunction, moduleName, name)
+		}
+		if err := fn.check(moduleName, def); err != nil {
+			return err
+		}
+	}
+	return nil
+}
+
+// CheckVersion returns an error unless the instantiated program [mod] exports
+// VersionFnName and it returns the version of the manifest, so programs built
+// against an older manifest are rejected before they run.
+func CheckVersion(ctx context.Context, mod api.Module) error {
+	fn := mod.ExportedFunction(VersionFnName)
+	if fn == nil {
+		return fmt.Errorf("%w: program does not export %s", ErrVersionMismatch, VersionFnName)
+	}
+	result, err := fn.Call(ctx)
+	if err != nil {
+		return fmt.Errorf("failed to read abi version: %w", err)
+	}
+	if len(result) != 1 || uint32(result[0]) != manifest.Version {
+		return fmt.Errorf("%w: program uses %v, runtime uses %d", ErrVersionMismatch, result, manifest.Version)
+	}
+	return nil
+}
+
+func (f *Function) check(moduleName string, def api.FunctionDefinition) error {
+	params := make([]string, len(f.Params))
+	for i, p := range f.Params {
+		params[i] = p.Type
+	}
+	if !typesMatch(params, def.ParamTypes()) || !typesMatch(f.Results, def.ResultTypes()) {
+		return fmt.Errorf("%w: %s.%s", ErrSignatureMismatch, moduleName, f.Name)
+	}
+	return nil
+}
+
+func typesMatch(types []string, valueTypes []api.ValueType) bool {
+	if len(types) != len(valueTypes) {
+		return false
+	}
+	for i, t := range types {
+		if valueType(t) != valueTypes[i] {
+			return false
+		}
+	}
+	return true
+}
+
+// valueType returns the wasm value type used to pass a manifest type.
+func valueType(t string) api.ValueType {
+	switch t {
+	case "i32", "u32", "ptr", "len":
+		return api.ValueTypeI32
+	case "i64", "u64":
+		return api.ValueTypeI64
+	default:
+		// not a valid value type, so never matches
+		return 0
+	}
+}
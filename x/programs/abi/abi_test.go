@@ -0,0 +1,51 @@
+// Copyright (C) 2023, Ava Labs, Inc. All rights reserved.
+// See the file LICENSE for licensing terms.
+
+package abi
+
+import (
+	"context"
+	"testing"
+
+	"github.com/stretchr/testify/require"
+	"github.com/tetratelabs/wazero"
+)
+
+// versionModule returns a module exporting a function [name] which returns
+// [version], which must be below 64 to be encoded in a single byte.
+func versionModule(name string, version byte) []byte {
+	module := []byte{
+		0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00,
+		// type section: func() i32
+		0x01, 0x05, 0x01, 0x60, 0x00, 0x01, 0x7f,
+		// function section: one function of type 0
+		0x03, 0x02, 0x01, 0x00,
+		// export section: function 0 as [name]
+		0x07, byte(len(name) + 4), 0x01, byte(len(name)),
+	}
+	module = append(module, name...)
+	return append(module,
+		0x00, 0x00,
+		// code section: i32.const [version]
+		0x0a, 0x06, 0x01, 0x04, 0x00, 0x41, version, 0x0b,
+	)
+}
+
+func TestCheckVersion(t *testing.T) {
+	require := require.New(t)
+	ctx := context.Background()
+
+	r := wazero.NewRuntime(ctx)
+	defer r.Close(ctx)
+
+	check := func(moduleName, name string, version byte) error {
+		config := wazero.NewModuleConfig().WithName(moduleName)
+		mod, err := r.InstantiateWithConfig(ctx, versionModule(name, version), config)
+		require.NoError(err)
+		return CheckVersion(ctx, mod)
+	}
+
+	require.NoError(check("current", VersionFnName, byte(Version())))
+	require.ErrorIs(check("older", VersionFnName, byte(Version()-1)), ErrVersionMismatch)
+	require.ErrorIs(check("unversioned", "get_guest", byte(Version())), ErrVersionMismatch)
+}
@@ -0,0 +1,95 @@
+// Copyright (C) 2023, Ava Labs, Inc. All rights reserved.
+// See the file LICENSE for licensing terms.
+
+package abi
+
+import (
+	"bufio"
+	"bytes"
+	"encoding/json"
+	"errors"
+	"fmt"
+	"hash/fnv"
+
+	"github.com/tetratelabs/wazero"
+)
+
+// ProgramSection is the custom section of a program's wasm module holding
+// the ABI of every function exposed by the SDK's expose macro.
+const ProgramSection = "wasmlanche_abi"
+
+var ErrSelectorMismatch = errors.New("program function selector mismatch")
+
+// ProgramFunction describes a function exposed by a program.
+type ProgramFunction struct {
+	// Name is the name of the function in the program's source.
+	Name string `json:"name"`
+	// Export is the name of the function exported by the module.
+	Export string `json:"export"`
+	// Selector is the method id the dispatch function of a program built with
+	// the SDK's program macro uses for the function, or nil if the function is
+	// exported on its own.
+	Selector *uint32 `json:"selector,omitempty"`
+	// Params are passed to the function in order in its argument buffer.
+	Params  []ProgramParam `json:"params"`
+	Returns string         `json:"returns"`
+	Docs    string         `json:"docs"`
+}
+
+// ProgramParam is a named parameter of a function exposed by a program. Type
+// is the Rust type of the parameter as written in the program's source.
+type ProgramParam struct {
+	Name string `json:"name"`
+	Type string `json:"type"`
+}
+
+// ProgramFunctions returns the functions exposed by the compiled program
+// [mod]. Programs built without the ABI section expose no functions.
+func ProgramFunctions(mod wazero.CompiledModule) ([]ProgramFunction, error) {
+	var functions []ProgramFunction
+	for _, section := range mod.CustomSections() {
+		if section.Name() != ProgramSection {
+			continue
+		}
+		parsed, err := ParseProgramFunctions(section.Data())
+		if err != nil {
+			return nil, err
+		}
+		functions = append(functions, parsed...)
+	}
+	return functions, nil
+}
+
+// ParseProgramFunctions parses the contents of a program's ABI section, which
+// holds a line of JSON for each exposed function.
+func ParseProgramFunctions(data []byte) ([]ProgramFunction, error) {
+	var functions []ProgramFunction
+	scanner := bufio.NewScanner(bytes.NewReader(data))
+	for scanner.Scan() {
+		line := bytes.TrimSpace(scanner.Bytes())
+		if len(line) == 0 {
+			continue
+		}
+		var fn ProgramFunction
+		if err := json.Unmarshal(line, &fn); err != nil {
+			return nil, fmt.Errorf("failed to parse program abi: %w", err)
+		}
+		// dispatch would call another method than the one described
+		if fn.Selector != nil && *fn.Selector != MethodID(fn.Name) {
+			return nil, fmt.Errorf("%w: %s: found %d, expected %d", ErrSelectorMismatch, fn.Name, *fn.Selector, MethodID(fn.Name))
+		}
+		functions = append(functions, fn)
+	}
+	if err := scanner.Err(); err != nil {
+		return nil, fmt.Errorf("failed to read program abi: %w", err)
+	}
+	return functions, nil
+}
+
+// MethodID returns the id the dispatch function of a program built with the
+// SDK's program macro uses for [method], the FNV-1a hash of its name.
+func MethodID(method string) uint32 {
+	h := fnv.New32a()
+	_, _ = h.Write([]byte(method))
+	return h.Sum32()
+}
@@ -0,0 +1,26 @@
+// Copyright (C) 2023, Ava Labs, Inc. All rights reserved.
+// See the file LICENSE for licensing terms.
+
+package abi
+
+import (
+	"testing"
+
+	"github.com/stretchr/testify/require"
+)
+
+func TestParseProgramFunctionsSelector(t *testing.T) {
+	require := require.New(t)
+
+	// the selector matches the method id the SDK computes for "transfer"
+	functions, err := ParseProgramFunctions([]byte(`{"name":"transfer","export":"dispatch","selector":3800399750,"params":[],"returns":"bool","docs":""}
+{"name":"get","export":"get_guest","params":[],"returns":"u64","docs":""}
+`))
+	require.NoError(err)
+	require.Len(functions, 2)
+	require.Equal(uint32(3800399750), *functions[0].Selector)
+	require.Nil(functions[1].Selector)
+
+	_, err = ParseProgramFunctions([]byte(`{"name":"transfer","export":"dispatch","selector":1,"params":[],"returns":"bool","docs":""}`))
+	require.ErrorIs(err, ErrSelectorMismatch)
+}
@@ -49,13 +49,13 @@ func (t *Token) Run(ctx context.Context) error {
 	db := utils.NewTestDB()
 	store := newProgramStorage(db)
 
-	runtime := runtime.New(t.log, meter, store)
-	err := runtime.Initialize(ctx, t.programBytes, functions)
+	rt := runtime.New(t.log, meter, store)
+	err := rt.Initialize(ctx, t.programBytes, functions)
 	if err != nil {
 		return err
 	}
 
-	result, err := runtime.Call(ctx, "init_program")
+	result, err := runtime.CallWithArgs(ctx, rt, "init_program", nil)
 	if err != nil {
 		return err
 	}
@@ -63,39 +63,42 @@ func (t *Token) Run(ctx context.Context) error {
 		zap.Int("gas", 0),
 	)
 
-	contract_id := result[0]
-	result, err = runtime.Call(ctx, "get_total_supply", contract_id)
+	contractID := result[0]
+	rt.SetProgramID(contractID)
+	_, err = runtime.CallWithArgs(ctx, rt, "get_total_supply", runtime.NewCallArgs().AddProgram(contractID).Bytes())
 	if err != nil {
 		return err
 	}
+	// the supply is an optional value, so it is only returned through the call result
+	supply, _ := rt.CallResult()
 	t.log.Debug("total supply",
-		zap.Uint64("minted", result[0]),
+		zap.Binary("minted", supply),
 	)
 
 	// generate alice keys
-	alicePtr, err := newKeyPtr(ctx, runtime)
+	alice, err := newKey()
 	if err != nil {
 		return err
 	}
 
 	// generate bob keys
-	bobPtr, err := newKeyPtr(ctx, runtime)
+	bob, err := newKey()
 	if err != nil {
 		return err
 	}
 
-	// check balance of alice
-	result, err = runtime.Call(ctx, "get_balance", contract_id, bobPtr)
+	// check balance of bob
+	result, err = runtime.CallWithArgs(ctx, rt, "get_balance", runtime.NewCallArgs().AddProgram(contractID).AddAddress(bob).Bytes())
 	if err != nil {
 		return err
 	}
 	t.log.Debug("balance",
-		zap.Int64("bob", int64(result[0])),
+		zap.Uint64("bob", result[0]),
 	)
 
 	// mint 100 tokens to alice
 	mintAlice := uint64(100)
-	_, err = runtime.Call(ctx, "mint_to", contract_id, alicePtr, mintAlice)
+	_, err = runtime.CallWithArgs(ctx, rt, "mint_to", runtime.NewCallArgs().AddProgram(contractID).AddAddress(alice).AddUint64(mintAlice).Bytes())
 	if err != nil {
 		return err
 	}
@@ -104,42 +107,27 @@ func (t *Token) Run(ctx context.Context) error {
 	)
 
 	// check balance of alice
-	result, err = runtime.Call(ctx, "get_balance", contract_id, alicePtr)
+	result, err = runtime.CallWithArgs(ctx, rt, "get_balance", runtime.NewCallArgs().AddProgram(contractID).AddAddress(alice).Bytes())
 	if err != nil {
 		return err
 	}
 	t.log.Debug("balance",
-		zap.Int64("alice", int64(result[0])),
+		zap.Uint64("alice", result[0]),
 	)
 
-	// deallocate bytes
-	defer func() {
-		_, err = runtime.Call(ctx, "dealloc", alicePtr, ed25519.PublicKeyLen)
-		if err != nil {
-			t.log.Error("failed to deallocate alice ptr",
-				zap.Error(err),
-			)
-		}
-		_, err = runtime.Call(ctx, "dealloc", bobPtr, ed25519.PublicKeyLen)
-		if err != nil {
-			t.log.Error("failed to deallocate bob ptr",
-				zap.Error(err),
-			)
-		}
-	}()
-
 	// check balance of bob
-	result, err = runtime.Call(ctx, "get_balance", contract_id, bobPtr)
+	result, err = runtime.CallWithArgs(ctx, rt, "get_balance", runtime.NewCallArgs().AddProgram(contractID).AddAddress(bob).Bytes())
 	if err != nil {
 		return err
 	}
 	t.log.Debug("balance",
-		zap.Int64("bob", int64(result[0])),
+		zap.Uint64("bob", result[0]),
 	)
 
-	// transfer 50 from alice to bob
+	// transfer 50 from alice to bob, which alice must sign
+	rt.SetActor(alice)
 	transferToBob := uint64(50)
-	_, err = runtime.Call(ctx, "transfer", contract_id, alicePtr, bobPtr, transferToBob)
+	_, err = runtime.CallWithArgs(ctx, rt, "transfer", runtime.NewCallArgs().AddProgram(contractID).AddAddress(alice).AddAddress(bob).AddUint64(transferToBob).Bytes())
 	if err != nil {
 		return err
 	}
@@ -147,32 +135,38 @@ func (t *Token) Run(ctx context.Context) error {
 		zap.Uint64("alice", transferToBob),
 		zap.Uint64("to bob", transferToBob),
 	)
+	for _, event := range rt.Events() {
+		t.log.Debug("event",
+			zap.Uint64("program", event.ProgramID),
+			zap.String("topic", event.Topic),
+		)
+	}
 
 	// get balance alice
-	result, err = runtime.Call(ctx, "get_balance", contract_id, alicePtr)
+	result, err = runtime.CallWithArgs(ctx, rt, "get_balance", runtime.NewCallArgs().AddProgram(contractID).AddAddress(alice).Bytes())
 	if err != nil {
 		return err
 	}
 	t.log.Debug("balance",
-		zap.Int64("alice", int64(result[0])),
+		zap.Uint64("alice", result[0]),
 	)
 
 	// get balance bob
-	result, err = runtime.Call(ctx, "get_balance", contract_id, bobPtr)
+	result, err = runtime.CallWithArgs(ctx, rt, "get_balance", runtime.NewCallArgs().AddProgram(contractID).AddAddress(bob).Bytes())
 	if err != nil {
 		return err
 	}
-	t.log.Debug("balance", zap.Int64("bob", int64(result[0])))
+	t.log.Debug("balance", zap.Uint64("bob", result[0]))
 
 	return nil
 }
 
-func newKeyPtr(ctx context.Context, runtime runtime.Runtime) (uint64, error) {
+func newKey() ([]byte, error) {
 	priv, err := ed25519.GeneratePrivateKey()
 	if err != nil {
-		return 0, err
+		return nil, err
 	}
 
 	pk := priv.PublicKey()
-	return runtime.WriteGuestBuffer(ctx, pk[:])
+	return pk[:], nil
 }
@@ -0,0 +1,28 @@
+// Copyright (C) 2023, Ava Labs, Inc. All rights reserved.
+// See the file LICENSE for licensing terms.
+
+package runtime
+
+import (
+	"context"
+	"testing"
+
+	"github.com/stretchr/testify/require"
+	"github.com/tetratelabs/wazero"
+)
+
+func TestHostModulesMatchABI(t *testing.T) {
+	require := require.New(t)
+	ctx := context.Background()
+
+	r := wazero.NewRuntime(ctx)
+	defer r.Close(ctx)
+
+	// Instantiate fails if a host module differs from the abi manifest.
+	require.NoError(NewMapModule(log, nil, &[]Event{}).Instantiate(ctx, r))
+	require.NoError(NewInvokeModule(log, nil, nil, nil, &CallContext{}, &Env{}, nil, &[]Event{}).Instantiate(ctx, r))
+	require.NoError(NewContextModule(log, &CallContext{}).Instantiate(ctx, r))
+	require.NoError(NewEnvModule(log, &Env{}).Instantiate(ctx, r))
+	require.NoError(NewEventModule(log, &CallContext{}, &[]Event{}).Instantiate(ctx, r))
+	require.NoError(NewMeterModule(log, NewMeter(log, 0, nil)).Instantiate(ctx, r))
+}
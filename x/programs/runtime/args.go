@@ -0,0 +1,111 @@
+// Copyright (C) 2023, Ava Labs, Inc. All rights reserved.
+// See the file LICENSE for licensing terms.
+
+package runtime
+
+import (
+	"context"
+	"encoding/binary"
+
+	"github.com/ava-labs/hypersdk/x/programs/abi"
+)
+
+// Tags the SDK writes before the bytes of each value.
+const (
+	tagInt     = 3
+	tagAddress = 4
+	tagProgram = 5
+	tagU64     = 12
+	tagU128    = 13
+	tagI128    = 14
+	tagU256    = 15
+)
+
+// CallArgs builds the argument buffer passed to functions exposed by a program.
+// Each argument is written as a big endian int64 length, a bool set for ints,
+// and the tagged bytes of the argument, matching the SDK's marshal_args.
+type CallArgs struct {
+	buf []byte
+}
+
+func NewCallArgs() *CallArgs {
+	return &CallArgs{}
+}
+
+// AddProgram adds the id of a program, decoded as a ProgramContext.
+func (a *CallArgs) AddProgram(id uint64) *CallArgs {
+	return a.add(false, tagProgram, binary.BigEndian.AppendUint64(nil, id))
+}
+
+// AddInt adds an int, decoded as an i64.
+func (a *CallArgs) AddInt(value int64) *CallArgs {
+	return a.add(true, tagInt, binary.BigEndian.AppendUint64(nil, uint64(value)))
+}
+
+// AddUint64 adds an unsigned int, decoded as a u64.
+func (a *CallArgs) AddUint64(value uint64) *CallArgs {
+	return a.add(true, tagU64, binary.BigEndian.AppendUint64(nil, value))
+}
+
+// AddUint128 adds the 16 big endian bytes of an unsigned int, decoded as a u128.
+func (a *CallArgs) AddUint128(value [16]byte) *CallArgs {
+	return a.add(true, tagU128, value[:])
+}
+
+// AddInt128 adds the 16 big endian two's complement bytes of a signed int,
+// decoded as an i128.
+func (a *CallArgs) AddInt128(value [16]byte) *CallArgs {
+	return a.add(true, tagI128, value[:])
+}
+
+// AddUint256 adds the 32 big endian bytes of an unsigned int, decoded as a U256.
+func (a *CallArgs) AddUint256(value [32]byte) *CallArgs {
+	return a.add(true, tagU256, value[:])
+}
+
+// AddAddress adds the bytes of an address, decoded as an Address.
+func (a *CallArgs) AddAddress(address []byte) *CallArgs {
+	return a.add(false, tagAddress, address)
+}
+
+// Bytes returns the arguments added so far.
+func (a *CallArgs) Bytes() []byte {
+	return a.buf
+}
+
+func (a *CallArgs) add(isInt bool, tag byte, value []byte) *CallArgs {
+	a.buf = binary.BigEndian.AppendUint64(a.buf, uint64(len(value)+1))
+	if isInt {
+		a.buf = append(a.buf, 1)
+	} else {
+		a.buf = append(a.buf, 0)
+	}
+	a.buf = append(a.buf, tag)
+	a.buf = append(a.buf, value...)
+	return a
+}
+
+// CallWithArgs writes [args] to the guest and calls the exposed function [name] with them.
+func CallWithArgs(ctx context.Context, r Runtime, name string, args []byte) ([]uint64, error) {
+	ptr, err := r.WriteGuestBuffer(ctx, args)
+	if err != nil {
+		return nil, err
+	}
+	return r.Call(ctx, name, ptr, uint64(len(args)))
+}
+
+// MethodID returns the id the dispatch function of a program built with the
+// SDK's program macro uses for [method], see [abi.MethodID].
+func MethodID(method string) uint32 {
+	return abi.MethodID(method)
+}
+
+// CallDispatch writes [args] to the guest and calls [method] through the
+// program's dispatch function.
+func CallDispatch(ctx context.Context, r Runtime, method string, args []byte) ([]uint64, error) {
+	ptr, err := r.WriteGuestBuffer(ctx, args)
+	if err != nil {
+		return nil, err
+	}
+	return r.Call(ctx, dispatchFnName, uint64(MethodID(method)), ptr, uint64(len(args)))
+}
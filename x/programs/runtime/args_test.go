@@ -0,0 +1,42 @@
+// Copyright (C) 2023, Ava Labs, Inc. All rights reserved.
+// See the file LICENSE for licensing terms.
+
+package runtime
+
+import (
+	"bytes"
+	"testing"
+
+	"github.com/stretchr/testify/require"
+)
+
+func TestCallArgs128(t *testing.T) {
+	require := require.New(t)
+
+	one := [16]byte{15: 1}
+	var minusOne [16]byte
+	copy(minusOne[:], bytes.Repeat([]byte{0xff}, 16))
+
+	tests := []struct {
+		name     string
+		args     *CallArgs
+		expected []byte
+	}{
+		{
+			name: "uint128",
+			args: NewCallArgs().AddUint128(one),
+			// length, int flag and the SDK's Tag::U128
+			expected: append([]byte{0, 0, 0, 0, 0, 0, 0, 17, 1, 13}, one[:]...),
+		},
+		{
+			name: "int128",
+			args: NewCallArgs().AddInt128(minusOne),
+			// length, int flag and the SDK's Tag::I128
+			expected: append([]byte{0, 0, 0, 0, 0, 0, 0, 17, 1, 14}, minusOne[:]...),
+		},
+	}
+
+	for _, tt := range tests {
+		require.Equal(tt.expected, tt.args.Bytes(), tt.name)
+	}
+}
@@ -0,0 +1,80 @@
+// Copyright (C) 2023, Ava Labs, Inc. All rights reserved.
+// See the file LICENSE for licensing terms.
+
+package runtime
+
+import (
+	"context"
+
+	"github.com/tetratelabs/wazero"
+	"github.com/tetratelabs/wazero/api"
+
+	"github.com/ava-labs/avalanchego/utils/logging"
+
+	"github.com/ava-labs/hypersdk/x/programs/abi"
+)
+
+const (
+	contextModuleName = "context"
+	contextOk         = 0
+	contextErr        = -1
+
+	// actorLen is the length of the actor's address, matching the SDK's Address.
+	actorLen = 32
+)
+
+// CallContext identifies who a program is running for.
+type CallContext struct {
+	// Actor is the address of the transaction signer. It is the same for every
+	// program in a chain of program to program calls.
+	Actor []byte
+	// ProgramID is the id of the running program, or 0 if the host does not know it,
+	// i.e. until SetProgramID is called on the runtime of the first program called.
+	ProgramID uint64
+	// Caller is the id of the program that invoked the running program, or 0 if
+	// the actor called it directly.
+	Caller uint64
+}
+
+type ContextModule struct {
+	callCtx *CallContext
+	log     logging.Logger
+}
+
+// NewContextModule returns a new context host module, which tells programs who
+// [callCtx] runs them for.
+func NewContextModule(log logging.Logger, callCtx *CallContext) *ContextModule {
+	return &ContextModule{
+		callCtx: callCtx,
+		log:     log,
+	}
+}
+
+func (m *ContextModule) Instantiate(ctx context.Context, r wazero.Runtime) error {
+	mod, err := r.NewHostModuleBuilder(contextModuleName).
+		NewFunctionBuilder().WithFunc(m.actorFn).Export("actor").
+		NewFunctionBuilder().WithFunc(m.callerFn).Export("caller").
+		Instantiate(ctx)
+	if err != nil {
+		return err
+	}
+
+	return abi.CheckHostModule(contextModuleName, mod)
+}
+
+// actorFn writes the address of the actor to the guest's memory at [actorPtr].
+func (m *ContextModule) actorFn(_ context.Context, mod api.Module, actorPtr uint32) int32 {
+	if len(m.callCtx.Actor) != actorLen {
+		return contextErr
+	}
+	if !mod.Memory().Write(actorPtr, m.callCtx.Actor) {
+		return contextErr
+	}
+	return contextOk
+}
+
+// callerFn returns the id of the program that invoked the running program, or 0
+// if the actor called it directly.
+func (m *ContextModule) callerFn(_ context.Context, _ api.Module) int64 {
+	return int64(m.callCtx.Caller)
+}
@@ -0,0 +1,84 @@
+// Copyright (C) 2023, Ava Labs, Inc. All rights reserved.
+// See the file LICENSE for licensing terms.
+
+package runtime
+
+import (
+	"context"
+
+	"github.com/tetratelabs/wazero"
+	"github.com/tetratelabs/wazero/api"
+
+	"github.com/ava-labs/avalanchego/ids"
+	"github.com/ava-labs/avalanchego/utils/logging"
+
+	"github.com/ava-labs/hypersdk/x/programs/abi"
+)
+
+const (
+	envModuleName = "env"
+	envOk         = 0
+	envErr        = -1
+)
+
+// Env describes the block and transaction a program runs in.
+type Env struct {
+	Height uint64
+	// Timestamp is the time of the block in milliseconds since the unix epoch.
+	Timestamp int64
+	ChainID   ids.ID
+	TxID      ids.ID
+}
+
+type EnvModule struct {
+	env *Env
+	log logging.Logger
+}
+
+// NewEnvModule returns a new env host module, which tells programs about the
+// block and transaction [env] describes.
+func NewEnvModule(log logging.Logger, env *Env) *EnvModule {
+	return &EnvModule{
+		env: env,
+		log: log,
+	}
+}
+
+func (m *EnvModule) Instantiate(ctx context.Context, r wazero.Runtime) error {
+	mod, err := r.NewHostModuleBuilder(envModuleName).
+		NewFunctionBuilder().WithFunc(m.heightFn).Export("height").
+		NewFunctionBuilder().WithFunc(m.timestampFn).Export("timestamp").
+		NewFunctionBuilder().WithFunc(m.chainIDFn).Export("chain_id").
+		NewFunctionBuilder().WithFunc(m.txIDFn).Export("tx_id").
+		Instantiate(ctx)
+	if err != nil {
+		return err
+	}
+
+	return abi.CheckHostModule(envModuleName, mod)
+}
+
+func (m *EnvModule) heightFn(_ context.Context, _ api.Module) int64 {
+	return int64(m.env.Height)
+}
+
+func (m *EnvModule) timestampFn(_ context.Context, _ api.Module) int64 {
+	return m.env.Timestamp
+}
+
+// chainIDFn writes the id of the chain to the guest's memory at [idPtr].
+func (m *EnvModule) chainIDFn(_ context.Context, mod api.Module, idPtr uint32) int32 {
+	return writeID(mod, idPtr, m.env.ChainID)
+}
+
+// txIDFn writes the id of the transaction to the guest's memory at [idPtr].
+func (m *EnvModule) txIDFn(_ context.Context, mod api.Module, idPtr uint32) int32 {
+	return writeID(mod, idPtr, m.env.TxID)
+}
+
+func writeID(mod api.Module, ptr uint32, id ids.ID) int32 {
+	if !mod.Memory().Write(ptr, id[:]) {
+		return envErr
+	}
+	return envOk
+}
@@ -0,0 +1,92 @@
+// Copyright (C) 2023, Ava Labs, Inc. All rights reserved.
+// See the file LICENSE for licensing terms.
+
+package runtime
+
+import (
+	"context"
+
+	"github.com/tetratelabs/wazero"
+	"github.com/tetratelabs/wazero/api"
+
+	"github.com/ava-labs/avalanchego/utils/logging"
+
+	"github.com/ava-labs/hypersdk/x/programs/abi"
+	"github.com/ava-labs/hypersdk/x/programs/utils"
+)
+
+const (
+	eventModuleName = "event"
+	eventOk         = 0
+	eventErr        = -1
+)
+
+// Event is emitted by a program to tell indexers what it did.
+type Event struct {
+	// ProgramID is the id the host runs the program that emitted the event by.
+	ProgramID uint64
+	Topic     string
+	// Payload holds the fields of the event, packed as the SDK packs stored structs.
+	Payload []byte
+}
+
+type EventModule struct {
+	// who the program running in this module's runtime runs for
+	callCtx *CallContext
+	events  *[]Event
+	log     logging.Logger
+}
+
+// NewEventModule returns a new event host module, which appends the events
+// programs emit to [events].
+func NewEventModule(log logging.Logger, callCtx *CallContext, events *[]Event) *EventModule {
+	return &EventModule{
+		callCtx: callCtx,
+		events:  events,
+		log:     log,
+	}
+}
+
+func (m *EventModule) Instantiate(ctx context.Context, r wazero.Runtime) error {
+	mod, err := r.NewHostModuleBuilder(eventModuleName).
+		NewFunctionBuilder().WithFunc(m.emitEventFn).Export("emit_event").
+		Instantiate(ctx)
+	if err != nil {
+		return err
+	}
+
+	return abi.CheckHostModule(eventModuleName, mod)
+}
+
+// emitEventFn records an event with the topic and payload read from the guest's
+// memory. The event belongs to the id the host knows the running program by,
+// never the id the program passes, so programs can not emit events as another.
+func (m *EventModule) emitEventFn(
+	_ context.Context,
+	mod api.Module,
+	_ uint64,
+	topicPtr,
+	topicLen,
+	payloadPtr,
+	payloadLen uint32,
+) int32 {
+	topic, ok := utils.GetBuffer(mod, topicPtr, topicLen)
+	if !ok {
+		return eventErr
+	}
+	payload, ok := utils.GetBuffer(mod, payloadPtr, payloadLen)
+	if !ok {
+		return eventErr
+	}
+
+	// Need to copy the payload because the guest can reuse the memory after this function returns
+	copiedPayload := make([]byte, len(payload))
+	copy(copiedPayload, payload)
+
+	*m.events = append(*m.events, Event{
+		ProgramID: m.callCtx.ProgramID,
+		Topic:     string(topic),
+		Payload:   copiedPayload,
+	})
+	return eventOk
+}
@@ -0,0 +1,26 @@
+// Copyright (C) 2023, Ava Labs, Inc. All rights reserved.
+// See the file LICENSE for licensing terms.
+
+package runtime
+
+import (
+	"context"
+	"testing"
+
+	"github.com/stretchr/testify/require"
+)
+
+func TestEventBelongsToRunningProgram(t *testing.T) {
+	require := require.New(t)
+	ctx := context.Background()
+
+	// the program's memory holds "kvw", read as the topic and payload
+	rt := New(log, NewMeter(log, 0, nil), nil)
+	require.NoError(rt.Initialize(ctx, invokeProgramBytes, nil))
+	rt.SetProgramID(1)
+	m := NewEventModule(log, &rt.callCtx, rt.events)
+
+	// program 1 emits an event passing the id of program 2
+	require.Equal(int32(eventOk), m.emitEventFn(ctx, rt.mod, 2, 0, 1, 1, 1))
+	require.Equal([]Event{{ProgramID: 1, Topic: "k", Payload: []byte("v")}}, rt.Events())
+}
@@ -5,11 +5,15 @@ package runtime
 
 import (
 	"context"
+	"encoding/binary"
+	"sort"
+	"strings"
 
 	"github.com/tetratelabs/wazero"
 	"github.com/tetratelabs/wazero/api"
 
 	"github.com/ava-labs/avalanchego/utils/logging"
+	"github.com/ava-labs/hypersdk/x/programs/abi"
 	"github.com/ava-labs/hypersdk/x/programs/utils"
 )
 
@@ -17,31 +21,74 @@ const (
 	mapModuleName = "map"
 	mapOk         = 0
 	mapErr        = -1
+	// mapNotFound is returned when no value is stored at the requested key.
+	mapNotFound = -2
+	// mapUnknownProgram is returned when no storage exists for the program id.
+	mapUnknownProgram = -3
 )
 
 type maps map[string][]byte
 
+// change records the value a key held before it was stored or deleted, so
+// reverting can restore it.
+type change struct {
+	id      uint64
+	key     string
+	value   []byte
+	existed bool
+}
+
 // Key value store for program data
 type storage struct {
 	// uint64 for simplicity, could be a real hash later
 	state   map[uint64]maps
 	mods    map[uint64]api.Module
 	counter uint64
+	// every change to state, so the position in it is a savepoint
+	journal []change
+}
+
+// record adds the value [key] holds in the storage of [id] to the journal.
+func (s *storage) record(id uint64, key string) {
+	value, existed := s.state[id][key]
+	s.journal = append(s.journal, change{
+		id:      id,
+		key:     key,
+		value:   value,
+		existed: existed,
+	})
+}
+
+// revert undoes every change made after [savepoint].
+func (s *storage) revert(savepoint int) {
+	for i := len(s.journal) - 1; i >= savepoint; i-- {
+		c := s.journal[i]
+		if c.existed {
+			s.state[c.id][c.key] = c.value
+		} else {
+			delete(s.state[c.id], c.key)
+		}
+	}
+	s.journal = s.journal[:savepoint]
 }
 
 type MapModule struct {
 	meter Meter
 	log   logging.Logger
 	store storage
+	// the events emitted by every program of the transaction, which savepoints cover
+	events *[]Event
 }
 
 // NewMapModule returns a new map host module which can manage in memory state.
 // This is a placeholder storage system intended to show how a wasm program
-// would access/modify persistent storage.
-func NewMapModule(log logging.Logger, meter Meter) *MapModule {
+// would access/modify persistent storage. Reverting to a savepoint also drops
+// the events appended to [events] since it was taken.
+func NewMapModule(log logging.Logger, meter Meter, events *[]Event) *MapModule {
 	return &MapModule{
-		meter: meter,
-		log:   log,
+		meter:  meter,
+		log:    log,
+		events: events,
 		store: storage{
 			state:   make(map[uint64]maps),
 			mods:    make(map[uint64]api.Module),
@@ -51,14 +98,21 @@ func NewMapModule(log logging.Logger, meter Meter) *MapModule {
 }
 
 func (m *MapModule) Instantiate(ctx context.Context, r wazero.Runtime) error {
-	_, err := r.NewHostModuleBuilder(mapModuleName).
+	mod, err := r.NewHostModuleBuilder(mapModuleName).
 		NewFunctionBuilder().WithFunc(m.initializeFn).Export("init_program").
 		NewFunctionBuilder().WithFunc(m.storeBytesFn).Export("store_bytes").
 		NewFunctionBuilder().WithFunc(m.getBytesLenFn).Export("get_bytes_len").
 		NewFunctionBuilder().WithFunc(m.getBytesFn).Export("get_bytes").
+		NewFunctionBuilder().WithFunc(m.deleteBytesFn).Export("delete_bytes").
+		NewFunctionBuilder().WithFunc(m.iterPrefixFn).Export("iter_prefix").
+		NewFunctionBuilder().WithFunc(m.savepointFn).Export("savepoint").
+		NewFunctionBuilder().WithFunc(m.revertFn).Export("revert").
 		Instantiate(ctx)
+	if err != nil {
+		return err
+	}
 
-	return err
+	return abi.CheckHostModule(mapModuleName, mod)
 }
 
 func (m *MapModule) initializeFn(_ context.Context, mod api.Module) uint64 {
@@ -71,7 +125,7 @@ func (m *MapModule) initializeFn(_ context.Context, mod api.Module) uint64 {
 func (m *MapModule) storeBytesFn(_ context.Context, mod api.Module, id uint64, keyPtr uint32, keyLength uint32, valuePtr uint32, valueLength uint32) int32 {
 	_, ok := m.store.state[id]
 	if !ok {
-		return mapErr
+		return mapUnknownProgram
 	}
 
 	keyBuf, ok := utils.GetBuffer(mod, keyPtr, keyLength)
@@ -88,6 +142,7 @@ func (m *MapModule) storeBytesFn(_ context.Context, mod api.Module, id uint64, k
 	copiedValue := make([]byte, len(valBuf))
 	copy(copiedValue, valBuf)
 
+	m.store.record(id, string(keyBuf))
 	m.store.state[id][string(keyBuf)] = copiedValue
 	return mapOk
 }
@@ -95,7 +150,7 @@ func (m *MapModule) storeBytesFn(_ context.Context, mod api.Module, id uint64, k
 func (m *MapModule) getBytesLenFn(_ context.Context, mod api.Module, id uint64, keyPtr uint32, keyLength uint32) int32 {
 	_, ok := m.store.state[id]
 	if !ok {
-		return mapErr
+		return mapUnknownProgram
 	}
 	buf, ok := utils.GetBuffer(mod, keyPtr, keyLength)
 	if !ok {
@@ -103,7 +158,7 @@ func (m *MapModule) getBytesLenFn(_ context.Context, mod api.Module, id uint64,
 	}
 	val, ok := m.store.state[id][string(buf)]
 	if !ok {
-		return mapErr
+		return mapNotFound
 	}
 	return int32(len(val))
 }
@@ -115,7 +170,7 @@ func (m *MapModule) getBytesFn(ctx context.Context, mod api.Module, id uint64, k
 	}
 	_, ok := m.store.state[id]
 	if !ok {
-		return mapErr
+		return mapUnknownProgram
 	}
 	buf, ok := utils.GetBuffer(mod, keyPtr, uint32(keyLength))
 	if !ok {
@@ -123,7 +178,7 @@ func (m *MapModule) getBytesFn(ctx context.Context, mod api.Module, id uint64, k
 	}
 	val, ok := m.store.state[id][string(buf)]
 	if !ok {
-		return mapErr
+		return mapNotFound
 	}
 
 	result, err := mod.ExportedFunction("alloc").Call(ctx, uint64(valLength))
@@ -138,3 +193,115 @@ func (m *MapModule) getBytesFn(ctx context.Context, mod api.Module, id uint64, k
 
 	return int32(ptr)
 }
+
+func (m *MapModule) deleteBytesFn(_ context.Context, mod api.Module, id uint64, keyPtr uint32, keyLength uint32) int32 {
+	_, ok := m.store.state[id]
+	if !ok {
+		return mapUnknownProgram
+	}
+	buf, ok := utils.GetBuffer(mod, keyPtr, keyLength)
+	if !ok {
+		return mapErr
+	}
+	_, ok = m.store.state[id][string(buf)]
+	if !ok {
+		return mapNotFound
+	}
+
+	m.store.record(id, string(buf))
+	delete(m.store.state[id], string(buf))
+	return mapOk
+}
+
+// savepointFn returns a savepoint of the values stored by every program and the
+// events emitted, which revertFn restores. The number of events is packed in the
+// upper 32 bits and the length of the journal in the lower 32 bits.
+func (m *MapModule) savepointFn(_ context.Context, _ api.Module) int64 {
+	return int64(len(*m.events))<<32 | int64(len(m.store.journal))
+}
+
+// revertFn discards every value stored or deleted and every event emitted since
+// [savepoint] was taken, along with any savepoints taken after it.
+func (m *MapModule) revertFn(_ context.Context, _ api.Module, savepoint int64) int32 {
+	if savepoint < 0 {
+		return mapErr
+	}
+	emitted, changed := int(savepoint>>32), int(savepoint&0xffffffff)
+	if emitted > len(*m.events) || changed > len(m.store.journal) {
+		return mapErr
+	}
+	m.store.revert(changed)
+	*m.events = (*m.events)[:emitted]
+	return mapOk
+}
+
+// iterPrefixFn writes up to [limit] entries whose keys start with the prefix to
+// the guest, in ascending key order. Keys must be >= start and < end unless the
+// bound is empty. Each entry is written as a big endian uint32 key length, the
+// key, a big endian uint32 value length and the value. Returns the offset of
+// the buffer in the upper 32 bits and its length in the lower 32 bits.
+func (m *MapModule) iterPrefixFn(
+	ctx context.Context,
+	mod api.Module,
+	id uint64,
+	prefixPtr,
+	prefixLength,
+	startPtr,
+	startLength,
+	endPtr,
+	endLength,
+	limit uint32,
+) int64 {
+	state, ok := m.store.state[id]
+	if !ok {
+		return mapUnknownProgram
+	}
+	prefix, ok := utils.GetBuffer(mod, prefixPtr, prefixLength)
+	if !ok {
+		return mapErr
+	}
+	start, ok := utils.GetBuffer(mod, startPtr, startLength)
+	if !ok {
+		return mapErr
+	}
+	end, ok := utils.GetBuffer(mod, endPtr, endLength)
+	if !ok {
+		return mapErr
+	}
+
+	keys := []string{}
+	for key := range state {
+		if !strings.HasPrefix(key, string(prefix)) {
+			continue
+		}
+		if len(start) > 0 && key < string(start) {
+			continue
+		}
+		if len(end) > 0 && key >= string(end) {
+			continue
+		}
+		keys = append(keys, key)
+	}
+	sort.Strings(keys)
+	if len(keys) > int(limit) {
+		keys = keys[:limit]
+	}
+	if len(keys) == 0 {
+		return 0
+	}
+
+	buf := []byte{}
+	for _, key := range keys {
+		val := state[key]
+		buf = binary.BigEndian.AppendUint32(buf, uint32(len(key)))
+		buf = append(buf, key...)
+		buf = binary.BigEndian.AppendUint32(buf, uint32(len(val)))
+		buf = append(buf, val...)
+	}
+
+	ptr, err := utils.WriteBuffer(ctx, mod, buf)
+	if err != nil {
+		return mapErr
+	}
+	return int64(ptr<<32 | uint64(len(buf)))
+}
@@ -0,0 +1,49 @@
+// Copyright (C) 2023, Ava Labs, Inc. All rights reserved.
+// See the file LICENSE for licensing terms.
+
+package runtime
+
+import (
+	"context"
+	"testing"
+
+	"github.com/stretchr/testify/require"
+)
+
+func TestStorageRevert(t *testing.T) {
+	require := require.New(t)
+
+	s := storage{state: map[uint64]maps{1: {"kept": []byte("a")}}}
+	savepoint := len(s.journal)
+
+	s.record(1, "kept")
+	s.state[1]["kept"] = []byte("b")
+	s.record(1, "added")
+	s.state[1]["added"] = []byte("c")
+	s.revert(savepoint)
+
+	require.Equal(maps{"kept": []byte("a")}, s.state[1])
+	require.Empty(s.journal)
+}
+
+func TestRevertDropsEvents(t *testing.T) {
+	require := require.New(t)
+	ctx := context.Background()
+
+	events := []Event{{ProgramID: 1, Topic: "kept"}}
+	m := NewMapModule(log, nil, &events)
+	m.store.state[1] = maps{}
+	savepoint := m.savepointFn(ctx, nil)
+
+	m.store.record(1, "added")
+	m.store.state[1]["added"] = []byte("a")
+	events = append(events, Event{ProgramID: 1, Topic: "dropped"})
+	require.Equal(int32(mapOk), m.revertFn(ctx, nil, savepoint))
+
+	require.Empty(m.store.state[1])
+	require.Equal([]Event{{ProgramID: 1, Topic: "kept"}}, events)
+
+	// savepoints taken after the one reverted to are gone
+	require.Equal(int32(mapErr), m.revertFn(ctx, nil, 2<<32|1))
+	require.Equal(int32(mapErr), m.revertFn(ctx, nil, -1))
+}
@@ -10,19 +10,27 @@ import (
 
 	"go.uber.org/zap"
 
+	"github.com/tetratelabs/wazero"
 	"github.com/tetratelabs/wazero/api"
 
 	"github.com/ava-labs/avalanchego/utils/logging"
+
+	"github.com/ava-labs/hypersdk/x/programs/abi"
 )
 
+const meterModuleName = "meter"
+
 var (
 	ErrMeterInsufficientBalance       = errors.New("operation failed insufficient balance")
 	_                           Meter = (*meter)(nil)
+	_                           Meter = (*limitedMeter)(nil)
 )
 
 type Meter interface {
 	api.Meter
 	GetBalance(context.Context) uint64
+	// Cost returns the units charged for [op].
+	Cost(op string) uint64
 }
 
 // NewMeter returns a meter capable of tracking the cost of operations.
@@ -68,8 +76,92 @@ func (m *meter) GetBalance(_ context.Context) uint64 {
 	return m.balance
 }
 
+func (m *meter) Cost(op string) uint64 {
+	return m.costMap[op]
+}
+
 func (m *meter) closed() error {
 	m.lock.RLock()
 	defer m.lock.RUnlock()
 	return m.initialError
 }
+
+// NewLimitedMeter returns a meter charging [parent] for every operation, which fails
+// once operations cost more than [maxUnits] in total even if [parent] has balance left.
+func NewLimitedMeter(parent Meter, maxUnits uint64) *limitedMeter {
+	return &limitedMeter{
+		parent:  parent,
+		balance: maxUnits,
+	}
+}
+
+type limitedMeter struct {
+	lock         sync.RWMutex
+	parent       Meter
+	balance      uint64
+	initialError error
+}
+
+func (m *limitedMeter) AddCost(ctx context.Context, op string) error {
+	m.lock.Lock()
+	defer m.lock.Unlock()
+	if m.initialError != nil {
+		return m.initialError
+	}
+
+	// the limit is checked first, so the parent is not charged for an operation
+	// the limit does not allow
+	fee := m.Cost(op)
+	if fee > m.balance {
+		m.initialError = ErrMeterInsufficientBalance
+		return m.initialError
+	}
+	if err := m.parent.AddCost(ctx, op); err != nil {
+		return err
+	}
+
+	m.balance -= fee
+	return nil
+}
+
+func (m *limitedMeter) Cost(op string) uint64 {
+	return m.parent.Cost(op)
+}
+
+func (m *limitedMeter) GetBalance(ctx context.Context) uint64 {
+	m.lock.RLock()
+	defer m.lock.RUnlock()
+	if balance := m.parent.GetBalance(ctx); balance < m.balance {
+		return balance
+	}
+	return m.balance
+}
+
+type MeterModule struct {
+	meter Meter
+	log   logging.Logger
+}
+
+// NewMeterModule returns a new meter host module, which tells programs how many
+// units [meter] has left.
+func NewMeterModule(log logging.Logger, meter Meter) *MeterModule {
+	return &MeterModule{
+		meter: meter,
+		log:   log,
+	}
+}
+
+func (m *MeterModule) Instantiate(ctx context.Context, r wazero.Runtime) error {
+	mod, err := r.NewHostModuleBuilder(meterModuleName).
+		NewFunctionBuilder().WithFunc(m.remainingUnitsFn).Export("remaining_units").
+		Instantiate(ctx)
+	if err != nil {
+		return err
+	}
+
+	return abi.CheckHostModule(meterModuleName, mod)
+}
+
+func (m *MeterModule) remainingUnitsFn(ctx context.Context, _ api.Module) int64 {
+	return int64(m.meter.GetBalance(ctx))
+}
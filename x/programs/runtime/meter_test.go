@@ -78,3 +78,23 @@ func TestMeterRuntimeStop(t *testing.T) {
 	err = meter.AddCost(ctx, "ConstI32 0x0")
 	require.NoError(err)
 }
+
+func TestLimitedMeter(t *testing.T) {
+	require := require.New(t)
+	ctx := context.Background()
+
+	parent := NewMeter(log, maxFee, costMap)
+	meter := NewLimitedMeter(parent, 1)
+	require.Equal(uint64(1), meter.GetBalance(ctx))
+
+	// operations are charged to the parent
+	require.NoError(meter.AddCost(ctx, "ConstI32 0x0"))
+	require.Equal(maxFee-1, parent.GetBalance(ctx))
+	require.Equal(uint64(0), meter.GetBalance(ctx))
+
+	// the limit is reached before the parent runs out, and the parent is not
+	// charged for the operation the limit rejected
+	require.ErrorIs(meter.AddCost(ctx, "ConstI32 0x1"), ErrMeterInsufficientBalance)
+	require.Equal(maxFee-1, parent.GetBalance(ctx))
+	require.NoError(parent.AddCost(ctx, "ConstI32 0x1"))
+}
@@ -5,7 +5,8 @@ package runtime
 
 import (
 	"context"
-	"fmt"
+	"errors"
+	"math"
 
 	"github.com/tetratelabs/wazero"
 	"github.com/tetratelabs/wazero/api"
@@ -13,43 +14,83 @@ import (
 	"github.com/ava-labs/avalanchego/utils/logging"
 
 	"github.com/ava-labs/hypersdk/chain"
-	"github.com/ava-labs/hypersdk/codec"
+	"github.com/ava-labs/hypersdk/x/programs/abi"
 	"github.com/ava-labs/hypersdk/x/programs/utils"
 )
 
 const (
-	invokeModuleName = "program_invoke"
+	invokeModuleName = "program"
 	invokeOK         = 0
 	invokeErr        = -1
+
+	// invokeTrap is returned when the callee traps or reverts.
+	invokeTrap = -2
+
+	// invokeUnknownMethod is returned when the callee does not export the method.
+	invokeUnknownMethod = -3
+
+	// invokeInvalidArgs is returned when the arguments can not be passed to the callee.
+	invokeInvalidArgs = -4
+
+	// invokeInsufficientUnits is returned when the meter runs out during the call.
+	invokeInsufficientUnits = -5
+
+	// invokeAlreadyInitialized is returned by the dispatch function of the callee
+	// when its #[init] method already ran, and passed on to the caller.
+	invokeAlreadyInitialized = -6
 )
 
 type InvokeModule struct {
 	db      chain.Database
 	meter   Meter
 	storage Storage
+	// who the program running in this module's runtime runs for
+	callCtx *CallContext
+	// the block and transaction the program running in this module's runtime runs in
+	env *Env
+	// the values stored by every program of the transaction
+	maps *MapModule
+	// the events emitted by every program of the transaction
+	events *[]Event
+	// tagged result set by the program running in this module's runtime
+	callResult []byte
+	// failure code set by the program running in this module's runtime
+	callError int64
 
 	log logging.Logger
 }
 
 // NewInvokeModule returns a new program invoke host module which can perform program to program calls.
-func NewInvokeModule(log logging.Logger, db chain.Database, meter Meter, storage Storage) *InvokeModule {
+func NewInvokeModule(log logging.Logger, db chain.Database, meter Meter, storage Storage, callCtx *CallContext, env *Env, maps *MapModule, events *[]Event) *InvokeModule {
 	return &InvokeModule{
 		db:      db,
 		meter:   meter,
 		storage: storage,
+		callCtx: callCtx,
+		env:     env,
+		maps:    maps,
+		events:  events,
 		log:     log,
 	}
 }
 
 func (m *InvokeModule) Instantiate(ctx context.Context, r wazero.Runtime) error {
-	_, err := r.NewHostModuleBuilder(invokeModuleName).
-		NewFunctionBuilder().WithFunc(m.programInvokeFn).Export(invokeModuleName).
+	mod, err := r.NewHostModuleBuilder(invokeModuleName).
+		NewFunctionBuilder().WithFunc(m.programInvokeFn).Export("invoke_program").
+		NewFunctionBuilder().WithFunc(m.programInvokeWithUnitsFn).Export("invoke_program_with_units").
+		NewFunctionBuilder().WithFunc(m.setCallResultFn).Export("set_call_result").
+		NewFunctionBuilder().WithFunc(m.setCallErrorFn).Export("set_call_error").
 		Instantiate(ctx)
+	if err != nil {
+		return err
+	}
 
-	return err
+	return abi.CheckHostModule(invokeModuleName, mod)
 }
 
 // programInvokeFn makes a call to an entry function of a program in the context of another program's ID.
+// The tagged result set by the callee is copied into the caller's memory. Returns the offset of the
+// copy in the upper 32 bits and its length in the lower 32 bits.
 func (m *InvokeModule) programInvokeFn(
 	ctx context.Context,
 	mod api.Module,
@@ -60,12 +101,57 @@ func (m *InvokeModule) programInvokeFn(
 	argsPtr,
 	argsLen uint32,
 ) int64 {
+	return m.invoke(ctx, mod, m.meter, programID, invokeProgramID, entryPtr, entryLen, argsPtr, argsLen)
+}
+
+// programInvokeWithUnitsFn makes a call like [programInvokeFn], which fails with
+// invokeInsufficientUnits once the callee consumes more than [maxUnits].
+func (m *InvokeModule) programInvokeWithUnitsFn(
+	ctx context.Context,
+	mod api.Module,
+	programID,
+	invokeProgramID uint64,
+	entryPtr,
+	entryLen,
+	argsPtr,
+	argsLen uint32,
+	maxUnits uint64,
+) int64 {
+	meter := NewLimitedMeter(m.meter, maxUnits)
+	return m.invoke(ctx, mod, meter, programID, invokeProgramID, entryPtr, entryLen, argsPtr, argsLen)
+}
+
+// invoke calls the entry function named at [entryPtr] of [invokeProgramID] in a new runtime metered by [meter].
+func (m *InvokeModule) invoke(
+	ctx context.Context,
+	mod api.Module,
+	meter Meter,
+	programID,
+	invokeProgramID uint64,
+	entryPtr,
+	entryLen,
+	argsPtr,
+	argsLen uint32,
+) (status int64) {
+	// a failed call leaves no changes behind
+	savepoint := m.maps.savepointFn(ctx, mod)
+	defer func() {
+		if status < invokeOK {
+			m.maps.revertFn(ctx, mod, savepoint)
+		}
+	}()
+
+	// programs are stored under 32 bit ids, so a larger id must not wrap to another program
+	if programID > math.MaxUint32 {
+		return invokeInvalidArgs
+	}
+
 	// get the entry function for invoke to call.
 	entryBuf, ok := utils.GetBuffer(mod, entryPtr, entryLen)
 	if !ok {
 		return invokeErr
 	}
-	entryFn := utils.GetGuestFnName(string(entryBuf))
+	method := string(entryBuf)
 
 	// get the program bytes stored in state
 	data, ok, err := m.storage.Get(ctx, uint32(programID))
@@ -76,58 +162,101 @@ func (m *InvokeModule) programInvokeFn(
 		return invokeErr
 	}
 
+	callCtx, ok := m.calleeContext(invokeProgramID)
+	if !ok {
+		return invokeErr
+	}
+
 	// create new runtime for the program invoke call
-	runtime := New(m.log, m.meter, m.storage)
+	runtime := New(m.log, meter, m.storage)
+	runtime.callCtx = callCtx
+	runtime.env = *m.env
+	runtime.events = m.events
+	runtime.maps = m.maps
 
-	// only export the function we are calling
-	exportedFunctions := []string{entryFn}
+	// only export the function we are calling, and alloc to pass it arguments
+	exportedFunctions := []string{allocFnName, dispatchFnName, method}
 	err = runtime.Initialize(ctx, data, exportedFunctions)
 	if err != nil {
 		return invokeErr
 	}
+	// programs built with the program macro check the method in their dispatch function
+	_, isDispatch := runtime.exported[dispatchFnName]
+	if _, ok := runtime.exported[method]; !ok && !isDispatch {
+		return invokeUnknownMethod
+	}
 
 	callArgsBuf, ok := utils.GetBuffer(mod, argsPtr, argsLen)
 	if !ok {
-		return invokeErr
+		return invokeInvalidArgs
 	}
 
-	// sync args to new runtime and return arguments to the invoke call
-	params, err := getCallArgs(ctx, runtime, callArgsBuf, invokeProgramID)
+	// the callee's context is passed ahead of the caller's arguments
+	args := NewCallArgs().AddProgram(invokeProgramID).Bytes()
+	args = append(args, callArgsBuf...)
+
+	if isDispatch {
+		_, err = CallDispatch(ctx, runtime, method, args)
+	} else {
+		_, err = CallWithArgs(ctx, runtime, method, args)
+	}
+	if errors.Is(err, ErrMeterInsufficientBalance) {
+		return invokeInsufficientUnits
+	}
 	if err != nil {
-		return invokeErr
+		return invokeTrap
+	}
+	if code, ok := runtime.CallError(); ok {
+		return code
 	}
 
-	res, err := runtime.Call(ctx, entryFn, params...)
+	result, ok := runtime.CallResult()
+	if !ok {
+		return invokeErr
+	}
+	ptr, err := utils.WriteBuffer(ctx, mod, result)
 	if err != nil {
 		return invokeErr
 	}
 
-	return int64(res[0])
+	return int64(ptr<<32 | uint64(len(result)))
 }
 
-func getCallArgs(ctx context.Context, runtime Runtime, buffer []byte, invokeProgramID uint64) ([]uint64, error) {
-	// first arg contains id of program to call
-	args := []uint64{invokeProgramID}
-
-	p := codec.NewReader(buffer, len(buffer))
-	for !p.Empty() {
-		size := p.UnpackInt64(true)
-		isInt := p.UnpackBool()
-		if isInt {
-			valueInt := p.UnpackUint64(true)
-			args = append(args, valueInt)
-		} else {
-			valueBytes := make([]byte, size)
-			p.UnpackFixedBytes(int(size), &valueBytes)
-			ptr, err := runtime.WriteGuestBuffer(ctx, valueBytes)
-			if err != nil {
-				return nil, err
-			}
-			args = append(args, ptr)
-		}
+// calleeContext returns the context [invokeProgramID] runs in when called by the
+// program running in this module's runtime. The caller is the id the host knows
+// the running program by, never the id the program passes, so programs can not
+// claim to be another. Returns false if the host does not know the running program.
+func (m *InvokeModule) calleeContext(invokeProgramID uint64) (CallContext, bool) {
+	if m.callCtx.ProgramID == 0 {
+		return CallContext{}, false
+	}
+	return CallContext{
+		Actor:     m.callCtx.Actor,
+		ProgramID: invokeProgramID,
+		Caller:    m.callCtx.ProgramID,
+	}, true
+}
+
+// setCallResultFn records the tagged result of the call currently executing in this runtime.
+func (m *InvokeModule) setCallResultFn(_ context.Context, mod api.Module, resultPtr uint32, resultLen uint32) int32 {
+	buf, ok := utils.GetBuffer(mod, resultPtr, resultLen)
+	if !ok {
+		return invokeErr
 	}
-	if p.Err() != nil {
-		return nil, fmt.Errorf("failed to unpack arguments: %w", p.Err())
+
+	// Need to copy the result because the guest can reuse the memory after this function returns
+	m.callResult = make([]byte, len(buf))
+	copy(m.callResult, buf)
+	return invokeOK
+}
+
+// setCallErrorFn fails the call currently executing in this runtime with [code],
+// which is returned to the calling program.
+func (m *InvokeModule) setCallErrorFn(_ context.Context, _ api.Module, code int64) int32 {
+	// only failure codes can be set
+	if code >= invokeOK {
+		return invokeErr
 	}
-	return args, nil
+	m.callError = code
+	return invokeOK
 }
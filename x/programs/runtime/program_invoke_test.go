@@ -0,0 +1,76 @@
+// Copyright (C) 2023, Ava Labs, Inc. All rights reserved.
+// See the file LICENSE for licensing terms.
+
+package runtime
+
+import (
+	"context"
+	_ "embed"
+	"math"
+	"testing"
+
+	"github.com/golang/mock/gomock"
+	"github.com/stretchr/testify/require"
+)
+
+//go:embed testdata/invoke_guest.wasm
+var invokeProgramBytes []byte
+
+func TestCalleeContext(t *testing.T) {
+	require := require.New(t)
+
+	actor := []byte("actor")
+	callCtx := &CallContext{Actor: actor}
+	m := NewInvokeModule(nil, nil, nil, nil, callCtx, &Env{}, nil, new([]Event))
+
+	// the running program must be known before it can invoke others
+	_, ok := m.calleeContext(2)
+	require.False(ok)
+
+	// the caller is the program the host runs, whatever id the program passes
+	callCtx.ProgramID = 1
+	calleeCtx, ok := m.calleeContext(2)
+	require.True(ok)
+	require.Equal(CallContext{Actor: actor, ProgramID: 2, Caller: 1}, calleeCtx)
+}
+
+func TestInvokeRejectsProgramIDOutOfRange(t *testing.T) {
+	require := require.New(t)
+
+	m := NewInvokeModule(nil, nil, nil, nil, &CallContext{ProgramID: 1}, &Env{}, NewMapModule(nil, nil, new([]Event)), new([]Event))
+
+	// the id is rejected before the module or storage are touched, so ids past
+	// uint32 can not wrap to the program stored under their low bits
+	status := m.invoke(context.Background(), nil, nil, math.MaxUint32+2, 2, 0, 0, 0, 0)
+	require.Equal(int64(invokeInvalidArgs), status)
+}
+
+func TestInvokeRevertsFailedCall(t *testing.T) {
+	require := require.New(t)
+	ctx := context.Background()
+
+	storage := NewMockStorage(gomock.NewController(t))
+	storage.EXPECT().Get(gomock.Any(), uint32(2)).Return(invokeProgramBytes, true, nil).AnyTimes()
+
+	// program 1 invokes program 2, which stores in the storage of program 1
+	meter := NewMeter(log, 0, nil)
+	caller := New(log, meter, storage)
+	require.NoError(caller.Initialize(ctx, invokeProgramBytes, []string{allocFnName}))
+	caller.SetProgramID(1)
+	caller.maps.store.state[1] = maps{}
+
+	invoke := func(method string) int64 {
+		ptr, err := caller.WriteGuestBuffer(ctx, []byte(method))
+		require.NoError(err)
+		return caller.invokeMod.invoke(ctx, caller.mod, meter, 2, 2, uint32(ptr), uint32(len(method)), 0, 0)
+	}
+
+	// the callee stores in the same map module as its caller
+	require.Greater(invoke("store"), int64(invokeOK))
+	require.Equal(maps{"k": []byte("v")}, caller.maps.store.state[1])
+
+	// and what it stored before failing is reverted
+	require.Equal(int64(invokeTrap), invoke("store_then_trap"))
+	require.Equal(maps{"k": []byte("v")}, caller.maps.store.state[1])
+	require.Len(caller.maps.store.journal, 1)
+}
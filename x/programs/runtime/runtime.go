@@ -15,32 +15,48 @@ import (
 	"github.com/ava-labs/avalanchego/utils/logging"
 
 	"github.com/ava-labs/hypersdk/chain"
+	"github.com/ava-labs/hypersdk/x/programs/abi"
 	"github.com/ava-labs/hypersdk/x/programs/utils"
 )
 
 const (
 	allocFnName   = "alloc"
 	deallocFnName = "dealloc"
+	// dispatchFnName is exported by programs built with the SDK's program macro.
+	dispatchFnName = "dispatch"
 )
 
 func New(log logging.Logger, meter Meter, storage Storage) *runtime {
+	events := new([]Event)
 	return &runtime{
 		log:      log,
 		meter:    meter,
 		storage:  storage,
 		exported: make(map[string]api.Function),
+		events:   events,
+		maps:     NewMapModule(log, meter, events),
 	}
 }
 
 type runtime struct {
 	cancelFn context.CancelFunc
 	engine   wazero.Runtime
+	compiled wazero.CompiledModule
 	mod      api.Module
 	meter    Meter
 	storage  Storage
 	// functions exported by this runtime
-	exported map[string]api.Function
-	db       chain.Database
+	exported  map[string]api.Function
+	db        chain.Database
+	invokeMod *InvokeModule
+	// who the program runs for, read by the context host module
+	callCtx CallContext
+	// the block and transaction the program runs in, read by the env host module
+	env Env
+	// the events emitted by the program and every program it invokes
+	events *[]Event
+	// the values stored by the program and every program it invokes
+	maps *MapModule
 
 	closed bool
 
@@ -50,18 +66,46 @@ type runtime struct {
 func (r *runtime) Initialize(ctx context.Context, programBytes []byte, functions []string) error {
 	ctx, r.cancelFn = context.WithCancel(ctx)
 
-	r.engine = wazero.NewRuntimeWithConfig(ctx, wazero.NewRuntimeConfigInterpreter())
+	// custom sections are kept to read the program's abi
+	r.engine = wazero.NewRuntimeWithConfig(ctx, wazero.NewRuntimeConfigInterpreter().WithCustomSections(true))
 
 	// register host modules
-	mapMod := NewMapModule(r.log, r.meter)
-	err := mapMod.Instantiate(ctx, r.engine)
+	err := r.maps.Instantiate(ctx, r.engine)
 	if err != nil {
 		return fmt.Errorf("failed to create map host module: %w", err)
 	}
 
+	// tell programs who they run for
+	contextMod := NewContextModule(r.log, &r.callCtx)
+	err = contextMod.Instantiate(ctx, r.engine)
+	if err != nil {
+		return fmt.Errorf("failed to create context host module: %w", err)
+	}
+
+	// tell programs about the block and transaction they run in
+	envMod := NewEnvModule(r.log, &r.env)
+	err = envMod.Instantiate(ctx, r.engine)
+	if err != nil {
+		return fmt.Errorf("failed to create env host module: %w", err)
+	}
+
+	// collect the events programs emit
+	eventMod := NewEventModule(r.log, &r.callCtx, r.events)
+	err = eventMod.Instantiate(ctx, r.engine)
+	if err != nil {
+		return fmt.Errorf("failed to create event host module: %w", err)
+	}
+
+	// tell programs how many units they have left
+	meterMod := NewMeterModule(r.log, r.meter)
+	err = meterMod.Instantiate(ctx, r.engine)
+	if err != nil {
+		return fmt.Errorf("failed to create meter host module: %w", err)
+	}
+
 	// enable program to program calls
-	invokeMod := NewInvokeModule(r.log, r.db, r.meter, r.storage)
-	err = invokeMod.Instantiate(ctx, r.engine)
+	r.invokeMod = NewInvokeModule(r.log, r.db, r.meter, r.storage, &r.callCtx, &r.env, r.maps, r.events)
+	err = r.invokeMod.Instantiate(ctx, r.engine)
 	if err != nil {
 		return fmt.Errorf("failed to create delegate host module: %w", err)
 	}
@@ -75,6 +119,14 @@ func (r *runtime) Initialize(ctx context.Context, programBytes []byte, functions
 		return fmt.Errorf("failed to compile wasm module: %w", err)
 	}
 
+	// reject programs built against a different host abi before running them
+	err = abi.CheckImports(compiledModule.ImportedFunctions())
+	if err != nil {
+		return fmt.Errorf("failed to link wasm module: %w", err)
+	}
+
+	r.compiled = compiledModule
+
 	// TODO: breakout config?
 	config := wazero.NewModuleConfig().
 		WithStdout(os.Stdout).
@@ -87,19 +139,55 @@ func (r *runtime) Initialize(ctx context.Context, programBytes []byte, functions
 	}
 	r.log.Debug("Instantiated module")
 
+	err = abi.CheckVersion(ctx, r.mod)
+	if err != nil {
+		return fmt.Errorf("failed to link wasm module: %w", err)
+	}
+
 	// TODO: cleanup
 	for _, name := range functions {
+		var fn api.Function
 		switch name {
-		case allocFnName, deallocFnName:
-			r.exported[name] = r.mod.ExportedFunction(name)
+		case allocFnName, deallocFnName, dispatchFnName:
+			fn = r.mod.ExportedFunction(name)
 		default:
-			r.exported[name] = r.mod.ExportedFunction(utils.GetGuestFnName(name))
+			fn = r.mod.ExportedFunction(utils.GetGuestFnName(name))
+		}
+		// skip functions the module does not export so Call reports them as missing
+		if fn == nil {
+			continue
 		}
+		r.exported[name] = fn
 	}
 
 	return nil
 }
 
+// SetActor sets the address of the transaction signer the program runs for,
+// which is passed on to every program it invokes.
+func (r *runtime) SetActor(actor []byte) {
+	r.callCtx.Actor = actor
+}
+
+// SetProgramID sets the id of the program the runtime runs, which programs it
+// invokes see as their caller. Programs can only invoke others once it is set.
+func (r *runtime) SetProgramID(id uint64) {
+	r.callCtx.ProgramID = id
+}
+
+// SetEnv sets the block and transaction the program runs in, which is passed
+// on to every program it invokes.
+func (r *runtime) SetEnv(env Env) {
+	r.env = env
+}
+
+// Events returns the events emitted by the program and the programs it invoked,
+// in the order they were emitted. The events of invoked programs that failed are
+// not included.
+func (r *runtime) Events() []Event {
+	return *r.events
+}
+
 func (r *runtime) Call(ctx context.Context, name string, params ...uint64) ([]uint64, error) {
 	if r.closed {
 		return nil, fmt.Errorf("failed to call: %s: runtime closed", name)
@@ -110,6 +198,12 @@ func (r *runtime) Call(ctx context.Context, name string, params ...uint64) ([]ui
 		return nil, fmt.Errorf("failed to find exported function: %s", name)
 	}
 
+	// results of a previous call must not be mistaken for this one's
+	if r.invokeMod != nil && name != allocFnName && name != deallocFnName {
+		r.invokeMod.callResult = nil
+		r.invokeMod.callError = invokeOK
+	}
+
 	result, err := api.Call(ctx, params...)
 	if err != nil {
 		return nil, fmt.Errorf("failed to call %s: %w", name, err)
@@ -118,6 +212,33 @@ func (r *runtime) Call(ctx context.Context, name string, params ...uint64) ([]ui
 	return result, nil
 }
 
+// CallResult returns the tagged result the program set during the last call,
+// or false if it did not set one.
+func (r *runtime) CallResult() ([]byte, bool) {
+	if r.invokeMod == nil || r.invokeMod.callResult == nil {
+		return nil, false
+	}
+	return r.invokeMod.callResult, true
+}
+
+// Functions returns the functions exposed by the program, as described by the
+// abi the SDK embeds in it.
+func (r *runtime) Functions() ([]abi.ProgramFunction, error) {
+	if r.compiled == nil {
+		return nil, fmt.Errorf("failed to read functions: runtime not initialized")
+	}
+	return abi.ProgramFunctions(r.compiled)
+}
+
+// CallError returns the failure code the program set during the last call, or
+// false if it did not set one.
+func (r *runtime) CallError() (int64, bool) {
+	if r.invokeMod == nil || r.invokeMod.callError == invokeOK {
+		return 0, false
+	}
+	return r.invokeMod.callError, true
+}
+
 func (r *runtime) GetGuestBuffer(offset uint32, length uint32) ([]byte, bool) {
 	// TODO: add fee
 	// r.meter.AddCost()
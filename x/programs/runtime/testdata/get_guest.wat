@@ -1,6 +1,7 @@
 (module $test
   (type (;0;) (func (result i32)))
   (export "get_guest" (func 0))
+  (export "abi_version" (func 1))
   (func (;0;) (type 0) (result i32)
     ;; initialize a local i32 variable
     (local i32)
@@ -8,4 +9,8 @@
     i32.const 1
     ;; return result
   )
+  (func (;1;) (type 0) (result i32)
+    ;; the version of abi.json
+    i32.const 2
+  )
 )
\ No newline at end of file
@@ -0,0 +1,52 @@
+(module $invoke
+  (type (;0;) (func (result i32)))
+  (type (;1;) (func (param i32) (result i32)))
+  (type (;2;) (func (param i32 i32)))
+  (type (;3;) (func (param i64 i32 i32 i32 i32) (result i32)))
+  (type (;4;) (func (param i32 i32) (result i32)))
+  (import "map" "store_bytes" (func (;0;) (type 3)))
+  (import "program" "set_call_result" (func (;1;) (type 4)))
+  (memory (;0;) 1)
+  (export "memory" (memory 0))
+  (export "abi_version" (func 2))
+  (export "alloc" (func 3))
+  (export "store_guest" (func 4))
+  (export "store_then_trap_guest" (func 5))
+  ;; the key, the values and the unit result
+  (data (;0;) (i32.const 0) "kvw\00")
+  (func (;2;) (type 0) (result i32)
+    ;; the version of abi.json
+    i32.const 2
+  )
+  (func (;3;) (type 1) (param i32) (result i32)
+    ;; every buffer is written past the data
+    i32.const 64
+  )
+  (func (;4;) (type 2) (param i32 i32)
+    ;; store "v" at "k" in the storage of program 1
+    i64.const 1
+    i32.const 0
+    i32.const 1
+    i32.const 1
+    i32.const 1
+    call 0
+    drop
+    ;; succeed with the unit result
+    i32.const 3
+    i32.const 1
+    call 1
+    drop
+  )
+  (func (;5;) (type 2) (param i32 i32)
+    ;; store "w" at "k" in the storage of program 1
+    i64.const 1
+    i32.const 0
+    i32.const 1
+    i32.const 2
+    i32.const 1
+    call 0
+    drop
+    ;; then fail the call
+    unreachable
+  )
+)
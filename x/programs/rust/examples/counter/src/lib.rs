@@ -1,33 +1,62 @@
-use expose_macro::expose;
-use wasmlanche_sdk::program::{Program, ProgramValue};
-use wasmlanche_sdk::store::ProgramContext;
-use wasmlanche_sdk::types::Address;
-
-/// Initializes the program. This program maps addresses with a count.
-#[expose]
-fn init_program() -> i64 {
-    let mut counter_program = Program::new();
-    counter_program.add_field(String::from("counter"), 0.into());
-    counter_program.add_field(String::from("counts"), ProgramValue::MapObject);
-    counter_program.publish().unwrap().into()
-}
+use expose_macro::program;
 
-/// Increments the count at the address by the amount.
-#[expose]
-fn inc(ctx: ProgramContext, to: Address, amount: i64) {
-    let counter = amount + value(ctx.clone(), to);
-    // dont check for error/ok
-    let _ = ctx.store_map_value(
-        "counts",
-        ProgramValue::from(to),
-        ProgramValue::IntObject(counter),
-    );
-}
+/// This program maps addresses with a count.
+#[program]
+pub mod counter {
+    use storable_macro::{state, Storable};
+    use wasmlanche_sdk::collections::StorageMap;
+    use wasmlanche_sdk::errors::{MathError, StorageError};
+    use wasmlanche_sdk::math;
+    use wasmlanche_sdk::store::ProgramContext;
+    use wasmlanche_sdk::types::Address;
+
+    /// The state of the counter program.
+    #[state]
+    struct Counter {
+        counter: i64,
+        /// The count of each address.
+        counts: StorageMap<Address, i64>,
+    }
+
+    /// Why incrementing a count failed.
+    #[derive(Storable, Debug)]
+    pub enum CounterError {
+        Math(MathError),
+        Storage(StorageError),
+    }
+
+    impl From<MathError> for CounterError {
+        fn from(err: MathError) -> Self {
+            CounterError::Math(err)
+        }
+    }
+
+    impl From<StorageError> for CounterError {
+        fn from(err: StorageError) -> Self {
+            CounterError::Storage(err)
+        }
+    }
+
+    /// Initializes the program.
+    #[init]
+    pub fn init(ctx: ProgramContext) -> bool {
+        Counter::init(ctx, 0).is_ok()
+    }
+
+    /// Increments the count at the address by the amount, failing if the count would overflow.
+    pub fn inc(ctx: ProgramContext, to: Address, amount: i64) -> Result<(), CounterError> {
+        let counter = math::checked_add(value(ctx.clone(), to), amount)?;
+        Counter::new(ctx).counts().insert(&to, &counter)?;
+        Ok(())
+    }
 
-/// Gets the count at the address.
-#[expose]
-fn value(ctx: ProgramContext, of: Address) -> i64 {
-    ctx.get_map_value("counts", ProgramValue::from(of))
-        .map(i64::from)
-        .unwrap_or(0)
+    /// Gets the count at the address.
+    pub fn value(ctx: ProgramContext, of: Address) -> i64 {
+        Counter::new(ctx)
+            .counts()
+            .get(&of)
+            .ok()
+            .flatten()
+            .unwrap_or(0)
+    }
 }
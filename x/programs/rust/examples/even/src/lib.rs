@@ -1,49 +1,125 @@
 /// Counter but only for even numbers
 use expose_macro::expose;
-use wasmlanche_sdk::program::{Program, ProgramValue};
+use storable_macro::{state, Storable};
+use wasmlanche_sdk::errors::{MathError, StorageError};
+use wasmlanche_sdk::math;
+use wasmlanche_sdk::program::ProgramValue;
 use wasmlanche_sdk::store::ProgramContext;
 use wasmlanche_sdk::types::Address;
 
+/// The state of the even program.
+#[state]
+struct Even {
+    /// The counter program incremented by this program, set after it is created.
+    #[state(skip)]
+    counter: ProgramContext,
+}
+
+/// The error returned by the counter program's `inc`, with the layout of its `CounterError`.
+#[derive(Storable)]
+enum CounterError {
+    Math(MathError),
+    Storage(StorageError),
+}
+
 #[expose]
 fn init_program() -> i64 {
-    let even_program = Program::new();
-    even_program.publish().unwrap().into()
+    Even::publish().unwrap().ctx().clone().into()
 }
 
 #[expose]
 fn set(ctx: ProgramContext, counter_ctx: ProgramContext) {
-    ctx.store_value("counter", &ProgramValue::ProgramObject(counter_ctx))
-        .expect("Failed to store token contract address");
+    Even::new(ctx)
+        .counter()
+        .set(&counter_ctx)
+        .expect("Failed to store counter program");
 }
 
 /// Calls the counter program to increment by twice the amount.
 #[expose]
 fn inc(ctx: ProgramContext, whose: Address, amt: i64) {
-    let call_ctx = match ctx.get_value("counter") {
-        Ok(value) => ProgramContext::from(value),
-        Err(_) => {
+    let call_ctx = match Even::new(ctx.clone()).counter().get() {
+        Ok(Some(value)) => value,
+        _ => {
             // Can return error here, up to smart contract designer. Skipping for now.
             return;
         }
     };
-    ctx.program_invoke(
+    let Ok(amt) = math::checked_mul(amt, 2) else {
+        return;
+    };
+    // Can return error here, up to smart contract designer. Skipping for now.
+    let _ = ctx.program_invoke::<Result<(), CounterError>>(
         &call_ctx,
         "inc",
-        &[ProgramValue::from(whose), ProgramValue::IntObject(amt * 2)],
+        &[ProgramValue::from(whose), ProgramValue::IntObject(amt)],
     );
 }
 
 /// Returns the value of whose's counter from the counter program.
 #[expose]
 fn value(ctx: ProgramContext, whose: Address) -> i64 {
-    let call_ctx = match ctx.get_value("counter") {
-        Ok(value) => ProgramContext::from(value),
-        Err(_) => {
+    let call_ctx = match Even::new(ctx.clone()).counter().get() {
+        Ok(Some(value)) => value,
+        _ => {
             // Can return error here, up to smart contract designer. Skipping for now.
             return 0;
         }
     };
 
-    let result = ctx.program_invoke(&call_ctx, "value", &[ProgramValue::from(whose)]);
-    i64::from(result)
+    ctx.program_invoke::<i64>(&call_ctx, "value", &[ProgramValue::from(whose)])
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wasmlanche_sdk::errors::CallError;
+    use wasmlanche_sdk::mock::{self, Entry};
+    use wasmlanche_sdk::program::Initialized;
+
+    const ALICE: Address = Address::new([1; 32]);
+
+    fn setup() -> ProgramContext {
+        let counter = mock::deploy(Entry::Dispatch(counter::counter::dispatch));
+        assert!(counter::counter::init(counter.clone()));
+        let ctx = ProgramContext::from(init_program());
+        set(ctx.clone(), counter);
+        ctx
+    }
+
+    #[test]
+    fn init_runs_once_through_dispatch() {
+        let deployed = mock::deploy(Entry::Dispatch(counter::counter::dispatch));
+        let initialized = mock::call::<Initialized<bool>>(&deployed, "init", &[]).unwrap();
+        assert!(initialized.result);
+        let created = initialized.program;
+        assert_ne!(created.program_id, deployed.program_id);
+        mock::register(&created, Entry::Dispatch(counter::counter::dispatch));
+        let value: i64 = mock::call(&created, "value", &[ALICE.into()]).unwrap();
+        assert_eq!(value, 0);
+
+        for program in [&deployed, &created] {
+            assert!(matches!(
+                mock::call::<Initialized<bool>>(program, "init", &[]),
+                Err(CallError::AlreadyInitialized)
+            ));
+        }
+    }
+
+    #[test]
+    fn inc_doubles_amount() {
+        let ctx = setup();
+        inc(ctx.clone(), ALICE, 3);
+        inc(ctx.clone(), ALICE, 4);
+        assert_eq!(value(ctx, ALICE), 14);
+    }
+
+    #[test]
+    fn inc_skips_overflowing_amount() {
+        let ctx = setup();
+        inc(ctx.clone(), ALICE, 1);
+        inc(ctx.clone(), ALICE, i64::MAX / 2 + 1);
+        assert_eq!(value(ctx, ALICE), 2);
+    }
 }
@@ -1,69 +1,99 @@
-/// Counter but only for even numbers
+/// A lottery that pays players a random amount of tokens from a token program.
 use expose_macro::expose;
-use wasmlanche_sdk::program::{Program, ProgramValue};
+use storable_macro::{state, Event};
+use wasmlanche_sdk::program::ProgramValue;
 use wasmlanche_sdk::store::{ProgramContext, Store};
 use wasmlanche_sdk::types::Address;
 
-// Define the name of the token contract in the programs storage map.
-static TOKEN_PROGRAM_NAME: &str = "token_contract";
+/// The most units the token contract may consume transferring winnings.
+const MAX_TRANSFER_UNITS: u64 = 100_000;
+
+/// Emitted when a player is paid their winnings.
+#[derive(Event)]
+pub struct Win {
+    pub player: Address,
+    pub amount: u64,
+}
+
+/// The state of the lottery program, set after it is created.
+#[state]
+struct Lottery {
+    /// The token program paying out winnings.
+    #[state(skip)]
+    token_contract: ProgramContext,
+    /// The address winnings are paid from.
+    #[state(skip)]
+    address: Address,
+}
 
 /// Initializes the program.
 #[expose]
 fn init_program() -> i64 {
-    let lottery_contract = Program::new();
-    lottery_contract.publish().unwrap().into()
+    Lottery::publish().unwrap().ctx().clone().into()
 }
 
 /// Sets the token contract address and the lotto address. This needs to be set
 /// before play can be called, otherwise there is no reference contract and address.
 #[expose]
 fn set(ctx: ProgramContext, counter_ctx: ProgramContext, lot_address: Address) {
-    ctx.store_value(
-        TOKEN_PROGRAM_NAME,
-        &ProgramValue::ProgramObject(counter_ctx),
-    )
-    .expect("Failed to store token contract address");
-    ctx.store_value("address", &ProgramValue::from(lot_address))
+    let lottery = Lottery::new(ctx);
+    lottery
+        .token_contract()
+        .set(&counter_ctx)
+        .expect("Failed to store token contract address");
+    lottery
+        .address()
+        .set(&lot_address)
         .expect("Failed to store address");
 }
 
 /// Randomly generates a number (1-100) and transfers those tokens to the player.
 /// Calls the token contract(which is an external program call using invoke) to
-/// transfer tokens to the player.
+/// transfer tokens to the player. The lotto address must first allow this program to
+/// transfer its tokens with the token contract's approve, or nothing is paid.
 #[expose]
 fn play(ctx: ProgramContext, player: Address) -> bool {
     let num = get_random_number(player);
     // If win transfer to player
-    let call_ctx = match ctx.get_value(TOKEN_PROGRAM_NAME) {
-        Ok(value) => ProgramContext::from(value),
-        Err(_) => {
+    let lottery = Lottery::new(ctx.clone());
+    let call_ctx = match lottery.token_contract().get() {
+        Ok(Some(value)) => value,
+        _ => {
             return false;
         }
     };
 
-    let lotto_addy = match ctx.get_value("address") {
-        Ok(value) => value,
-        Err(_) => {
+    let lotto_addy = match lottery.address().get() {
+        Ok(Some(value)) => value,
+        _ => {
             return false;
         }
     };
 
-    // Transfer
-    ctx.program_invoke(
-        &call_ctx,
-        "transfer",
-        &[
-            lotto_addy,
-            ProgramValue::from(player),
-            ProgramValue::IntObject(num),
-        ],
-    );
-    true
+    // Transfer, returning whether the token contract accepted it within its units
+    let paid = ctx
+        .program_invoke_with_units::<bool>(
+            MAX_TRANSFER_UNITS,
+            &call_ctx,
+            "transfer",
+            &[
+                ProgramValue::from(lotto_addy),
+                ProgramValue::from(player),
+                ProgramValue::from(num),
+            ],
+        )
+        .unwrap_or(false);
+    paid && ctx
+        .emit_event(&Win {
+            player,
+            amount: num,
+        })
+        .is_ok()
 }
 
 // Seeding WASM RNG with the the player's address(which is currently randomly generated from host)
 // For demo purposes only, as this isn't a true rng.
-fn get_random_number(seed: Address) -> i64 {
+fn get_random_number(seed: Address) -> u64 {
     use rand::Rng;
     use rand_chacha::rand_core::SeedableRng;
     use rand_chacha::ChaCha8Rng;
@@ -72,3 +102,91 @@ fn get_random_number(seed: Address) -> i64 {
     let mut rng = ChaCha8Rng::seed_from_u64(first_val.as_bytes()[1] as u64);
     rng.gen_range(0..100)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wasmlanche_sdk::mock::{self, Entry};
+
+    const LOTTO: Address = Address::new([1; 32]);
+    const PLAYER: Address = Address::new([2; 32]);
+
+    /// Deploys the token program holding [amount] coins of the lotto address.
+    fn deploy_token(amount: u64) -> ProgramContext {
+        let token = ProgramContext::from(token::init_program());
+        mock::register(
+            &token,
+            Entry::methods(|method, args_ptr, args_len| match method {
+                "transfer" => unsafe {
+                    token::transfer_guest(args_ptr, args_len);
+                    true
+                },
+                _ => false,
+            }),
+        );
+        assert!(token::mint_to(token.clone(), LOTTO, amount));
+        token
+    }
+
+    /// Deploys the lottery paying from the lotto address with [token].
+    fn deploy_lottery(token: &ProgramContext) -> ProgramContext {
+        let lottery = ProgramContext::from(init_program());
+        mock::register(
+            &lottery,
+            Entry::methods(|method, args_ptr, args_len| match method {
+                "play" => unsafe {
+                    play_guest(args_ptr, args_len);
+                    true
+                },
+                _ => false,
+            }),
+        );
+        set(lottery.clone(), token.clone(), LOTTO);
+        lottery
+    }
+
+    fn play_through_host(lottery: &ProgramContext) -> bool {
+        mock::call(lottery, "play", &[PLAYER.into()]).unwrap()
+    }
+
+    #[test]
+    fn play_pays_from_approved_allowance() {
+        let token = deploy_token(1000);
+        let lottery = deploy_lottery(&token);
+        let winnings = get_random_number(PLAYER);
+
+        // the lottery can not spend the lotto address' tokens before it is allowed to, even
+        // when the lotto address signs
+        mock::set_actor(LOTTO);
+        assert!(!play_through_host(&lottery));
+        assert!(token::approve(token.clone(), lottery.clone(), 1000));
+
+        mock::set_actor(PLAYER);
+        assert!(play_through_host(&lottery));
+        assert_eq!(token::get_balance(token.clone(), PLAYER), winnings);
+        assert_eq!(token::get_balance(token.clone(), LOTTO), 1000 - winnings);
+
+        let events = mock::events();
+        let win = events
+            .iter()
+            .find(|event| event.program_id == lottery.program_id)
+            .and_then(|event| event.decode::<Win>())
+            .expect("the lottery announces the win");
+        assert!(win.player == PLAYER && win.amount == winnings);
+    }
+
+    #[test]
+    fn play_stops_at_allowance() {
+        let token = deploy_token(1000);
+        let lottery = deploy_lottery(&token);
+        let winnings = get_random_number(PLAYER);
+
+        mock::set_actor(LOTTO);
+        assert!(token::approve(token.clone(), lottery.clone(), winnings));
+        mock::set_actor(PLAYER);
+        assert!(play_through_host(&lottery));
+        // the allowance is spent, unless nothing was won
+        assert_eq!(play_through_host(&lottery), winnings == 0);
+        assert_eq!(token::get_balance(token.clone(), PLAYER), winnings);
+    }
+}
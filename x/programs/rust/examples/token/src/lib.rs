@@ -1,86 +1,259 @@
-use wasmlanche_sdk::program::{Program, ProgramValue};
+use wasmlanche_sdk::collections::StorageMap;
+use wasmlanche_sdk::math;
 use wasmlanche_sdk::store::ProgramContext;
 use wasmlanche_sdk::types::Address;
 
 use expose_macro::expose;
+use storable_macro::{state, Event, Storable};
+
+/// The state of the token program.
+#[state]
+struct Token {
+    name: String,
+    symbol: String,
+    total_supply: u64,
+    /// The balance of each address holding tokens.
+    balances: StorageMap<Address, u64>,
+    /// The coins each owner allows a program to transfer.
+    allowances: StorageMap<Allowance, u64>,
+}
+
+/// The key of the coins [owner] allows the program [spender] to transfer.
+#[derive(Storable)]
+struct Allowance {
+    owner: Address,
+    spender: ProgramContext,
+}
+
+/// Emitted when coins move from one address to another.
+#[derive(Event)]
+pub struct Transfer {
+    pub from: Address,
+    pub to: Address,
+    pub amount: u64,
+}
 
 /// Initializes the contract with a name, symbol, and total supply.
-/// Technically adding the balances field is not necessary.
 #[expose]
 pub fn init_program() -> i64 {
-    let mut token_program = Program::new();
-    token_program.add_field(String::from("name"), "WasmCoin".into());
-    token_program.add_field(String::from("symbol"), "WACK".into());
-    token_program.add_field(String::from("total_supply"), 123456789.into());
-    token_program.add_field(String::from("balances"), ProgramValue::MapObject);
-    token_program.publish().unwrap().into()
+    Token::publish(String::from("WasmCoin"), String::from("WACK"), 123456789)
+        .unwrap()
+        .ctx()
+        .clone()
+        .into()
 }
 
-/// Gets total supply or -1 on error.
+/// Gets total supply, or None if it is not set or can not be read.
 #[expose]
-pub fn get_total_supply(ctx: ProgramContext) -> i64 {
-    ctx.get_value("total_supply")
-        .unwrap_or(ProgramValue::IntObject(-1))
-        .into()
+pub fn get_total_supply(ctx: ProgramContext) -> Option<u64> {
+    Token::new(ctx).total_supply().get().ok().flatten()
 }
 
 /// Adds amount coins to the recipients balance.
 #[expose]
-pub fn mint_to(ctx: ProgramContext, recipient: Address, amount: i64) -> bool {
-    let amount = amount
-        + ctx
-            .get_map_value("balances", recipient.into())
-            .map(i64::from)
-            .unwrap_or(0);
-
-    ctx.store_map_value(
-        "balances",
-        ProgramValue::from(recipient),
-        ProgramValue::IntObject(amount),
-    )
-    .is_ok()
+pub fn mint_to(ctx: ProgramContext, recipient: Address, amount: u64) -> bool {
+    let balances = Token::new(ctx).balances();
+    // a missing balance is zero, but a failed read must not reset it
+    let Ok(balance) = balances.get(&recipient) else {
+        return false;
+    };
+
+    let Ok(balance) = math::checked_add(balance.unwrap_or(0), amount) else {
+        return false;
+    };
+    balances.insert(&recipient, &balance).is_ok()
+}
+
+/// Allows the program spender to transfer up to amount of the signer's coins, replacing
+/// any amount allowed before. Returns whether successful.
+#[expose]
+pub fn approve(ctx: ProgramContext, spender: ProgramContext, amount: u64) -> bool {
+    // only the signer can allow their coins to be spent, not a program they call
+    if ctx.caller().is_some() {
+        return false;
+    }
+    let allowance = Allowance {
+        owner: ctx.actor(),
+        spender,
+    };
+    Token::new(ctx)
+        .allowances()
+        .insert(&allowance, &amount)
+        .is_ok()
 }
 
-/// Transfers amount coins from the sender to the recipient. Returns whether successful.
+/// Transfers amount coins from the sender to the recipient. The sender authorizes the
+/// transfer by signing a transaction calling the token program, and a program calling it
+/// may only transfer the coins the sender allowed it to with approve. Returns whether
+/// successful.
 #[expose]
-pub fn transfer(ctx: ProgramContext, sender: Address, recipient: Address, amount: i64) -> bool {
+pub fn transfer(ctx: ProgramContext, sender: Address, recipient: Address, amount: u64) -> bool {
+    let token = Token::new(ctx.clone());
+    // the allowance left to the calling program, which must cover the amount
+    let allowance = match ctx.caller() {
+        None if ctx.actor() == sender => None,
+        None => return false,
+        Some(spender) => {
+            let allowance = Allowance {
+                owner: sender,
+                spender,
+            };
+            let Ok(Some(allowed)) = token.allowances().get(&allowance) else {
+                return false;
+            };
+            let Ok(left) = math::checked_sub(allowed, amount) else {
+                return false;
+            };
+            Some((allowance, left))
+        }
+    };
     // require sender != recipient
     if sender == recipient {
         return false;
     }
+    let balances = token.balances();
     // ensure the sender has adequate balance
-    let sender_balance = ctx
-        .get_map_value("balances", ProgramValue::from(sender))
-        .map(i64::from)
-        .unwrap_or(-1);
+    let Ok(Some(sender_balance)) = balances.get(&sender) else {
+        return false;
+    };
 
-    if amount < 0 || sender_balance < amount {
+    let Ok(remaining) = math::checked_sub(sender_balance, amount) else {
         return false;
-    }
+    };
 
-    let recipient_balance = ctx
-        .get_map_value("balances", ProgramValue::from(recipient))
-        .map(i64::from)
-        .unwrap_or(0);
-    ctx.store_map_value(
-        "balances",
-        ProgramValue::from(sender),
-        ProgramValue::IntObject(sender_balance - amount),
-    )
-    .and_then(|_| {
-        ctx.store_map_value(
-            "balances",
-            ProgramValue::from(recipient),
-            ProgramValue::IntObject(recipient_balance + amount),
-        )
+    let Ok(recipient_balance) = balances.get(&recipient) else {
+        return false;
+    };
+    let Ok(recipient_balance) = math::checked_add(recipient_balance.unwrap_or(0), amount) else {
+        return false;
+    };
+    // the sender is only debited and the allowance spent if the recipient is credited,
+    // and the transfer is only announced if all happened
+    ctx.atomic(|ctx| -> Result<(), Box<dyn std::error::Error>> {
+        // don't keep empty balances around
+        if remaining == 0 {
+            balances.remove(&sender)?;
+        } else {
+            balances.insert(&sender, &remaining)?;
+        }
+        balances.insert(&recipient, &recipient_balance)?;
+        if let Some((allowance, left)) = &allowance {
+            token.allowances().insert(allowance, left)?;
+        }
+        ctx.emit_event(&Transfer {
+            from: sender,
+            to: recipient,
+            amount,
+        })?;
+        Ok(())
     })
     .is_ok()
 }
 
 /// Gets the balance of the recipient.
 #[expose]
-pub fn get_balance(ctx: ProgramContext, recipient: Address) -> i64 {
-    ctx.get_map_value("balances", ProgramValue::from(recipient))
-        .map(i64::from)
+pub fn get_balance(ctx: ProgramContext, recipient: Address) -> u64 {
+    Token::new(ctx)
+        .balances()
+        .get(&recipient)
+        .ok()
+        .flatten()
         .unwrap_or(0)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wasmlanche_sdk::errors::StorageError;
+    use wasmlanche_sdk::mock::{self, Entry};
+
+    const ALICE: Address = Address::new([1; 32]);
+    const BOB: Address = Address::new([2; 32]);
+
+    fn setup() -> ProgramContext {
+        mock::set_actor(ALICE);
+        let ctx = ProgramContext::from(init_program());
+        assert!(mint_to(ctx.clone(), ALICE, 100));
+        ctx
+    }
+
+    #[test]
+    fn transfer_moves_balance() {
+        let ctx = setup();
+        assert!(transfer(ctx.clone(), ALICE, BOB, 40));
+        assert_eq!(get_balance(ctx.clone(), ALICE), 60);
+        assert_eq!(get_balance(ctx.clone(), BOB), 40);
+
+        // an emptied balance is removed
+        assert!(transfer(ctx.clone(), ALICE, BOB, 60));
+        assert_eq!(
+            Token::new(ctx.clone()).balances().get(&ALICE).unwrap(),
+            None
+        );
+        assert_eq!(get_balance(ctx, BOB), 100);
+    }
+
+    #[test]
+    fn transfer_emits_event() {
+        let ctx = setup();
+        assert!(transfer(ctx.clone(), ALICE, BOB, 40));
+        // failed transfers emit nothing
+        assert!(!transfer(ctx.clone(), ALICE, BOB, 100));
+
+        let events = mock::events();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].program_id, ctx.program_id);
+        let event = events[0].decode::<Transfer>().unwrap();
+        assert!(event.from == ALICE && event.to == BOB);
+        assert_eq!(event.amount, 40);
+    }
+
+    #[test]
+    fn transfer_requires_sender_to_sign() {
+        let ctx = setup();
+        mock::set_actor(BOB);
+        assert!(!transfer(ctx.clone(), ALICE, BOB, 40));
+        assert_eq!(get_balance(ctx, ALICE), 100);
+    }
+
+    #[test]
+    fn transfer_rejects_insufficient_balance() {
+        let ctx = setup();
+        assert!(!transfer(ctx.clone(), ALICE, BOB, 101));
+        assert!(!transfer(ctx.clone(), ALICE, ALICE, 1));
+        assert_eq!(get_balance(ctx.clone(), ALICE), 100);
+        assert_eq!(get_balance(ctx, BOB), 0);
+    }
+
+    #[test]
+    fn failed_update_is_discarded() {
+        let ctx = setup();
+        let balances = Token::new(ctx.clone()).balances();
+        let updated: Result<(), _> = ctx.atomic(|_| {
+            balances.insert(&ALICE, &0)?;
+            balances.insert(&BOB, &100)?;
+            Err(StorageError::InvalidBytes())
+        });
+        assert!(updated.is_err());
+        assert_eq!(get_balance(ctx.clone(), ALICE), 100);
+        assert_eq!(get_balance(ctx, BOB), 0);
+    }
+
+    #[test]
+    fn get_balance_through_host() {
+        let ctx = setup();
+        mock::register(
+            &ctx,
+            Entry::methods(|method, args_ptr, args_len| match method {
+                "get_balance" => unsafe {
+                    get_balance_guest(args_ptr, args_len);
+                    true
+                },
+                _ => false,
+            }),
+        );
+        let balance: u64 = mock::call(&ctx, "get_balance", &[ALICE.into()]).unwrap();
+        assert_eq!(balance, 100);
+        assert!(mock::call::<u64>(&ctx, "mint_to", &[]).is_err());
+    }
+}
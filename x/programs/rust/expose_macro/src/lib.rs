@@ -1,73 +1,321 @@
 extern crate proc_macro;
 
-use core::panic;
-
 use proc_macro::TokenStream;
-use quote::{quote, ToTokens};
-use syn::{parse_macro_input, parse_str, FnArg, Ident, ItemFn, Pat, PatType, Type};
+use proc_macro2::{Literal, TokenStream as TokenStream2};
+use quote::{format_ident, quote, ToTokens};
+use serde_json::json;
+use syn::{
+    parse_macro_input, Attribute, Error, FnArg, Ident, Item, ItemFn, Lit, Meta, Pat, PatType,
+    PathArguments, ReturnType, Signature, Type, TypePath,
+};
+
+mod program;
+
+/// The custom section of the WASM module holding the ABI of every exposed function.
+const ABI_SECTION: &str = "wasmlanche_abi";
 
 /// An attribute procedural macro that can be used to expose a function to the host.
 /// It does so by wrapping the [item] tokenstream in a new function that can be called by the host.
 /// The wrapper function will have the same name as the original function, but with "_guest" appended to it.
-/// The wrapper functions parameters will be converted to WASM supported types. When called, the wrapper function
-/// calls the original function by converting the parameters back to their intended types using .into().
+/// The wrapper function takes a single argument buffer, passed as its offset and length, holding every parameter in
+/// the format written by `ProgramContext::program_invoke`. Each parameter is decoded with its `Store` implementation,
+/// and if any of them can not be decoded the call fails with `INVOKE_INVALID_ARGUMENTS` instead of calling the function.
+/// Parameters may use any pattern, including `_` and tuple or struct destructuring, since the decoded values are
+/// passed to the original function as is.
+/// The result is handed to the host with `set_call_result`, so other programs can decode it when invoking this function.
+/// Functions may return any type implementing `Store`, including `Option<T>` and `Result<T, E>`. Only WASM supported
+/// primitives are also returned directly by the wrapper, other results are only available through the host's return buffer.
+/// Functions returning a `Result` discard every value they stored or deleted when they return an `Err`, as if they ran
+/// in `ProgramContext::atomic`.
+///
+/// The ABI of the function is written to the `wasmlanche_abi` custom section of the WASM module as a single line of JSON
+/// holding its name, exported name, parameter names and types, return type and doc comments.
 #[proc_macro_attribute]
 pub fn expose(_: TokenStream, item: TokenStream) -> TokenStream {
     let input = parse_macro_input!(item as ItemFn);
-    let name = &input.sig.ident;
-    let input_args = &input.sig.inputs;
-    let new_name = Ident::new(&format!("{}_guest", name), name.span()); // Create a new name for the generated function(name that will be called by the host)
+    match expose_impl(&input) {
+        Ok(output) => output.into(),
+        Err(err) => {
+            // Keep the original function so the error is the only one reported.
+            let err = err.to_compile_error();
+            quote!(#input #err).into()
+        }
+    }
+}
 
-    let full_params = input_args.iter().map(|fn_arg| {
-        // A typed argument is a parameter. An untyped(reciever) argument is a self parameter.
-        if let FnArg::Typed(PatType { pat, ty, .. }) = fn_arg {
-            if let Pat::Ident(ref pat_ident) = **pat {
-                let param_name = &pat_ident.ident;
-                // We only set the type to i64 if it is not a supported WASM primitive.
-                let param_type = if is_supported_primitive(ty) {
-                    ty.to_token_stream()
-                } else {
-                    parse_str::<Type>("i64")
-                        .expect("valid i64 type")
-                        .to_token_stream()
-                };
-                return (param_name, param_type);
-            }
-            // Explicitly note this will panic on _ parameters.
-            if let Pat::Wild(_) = **pat {
-                panic!("Unused variables not supported.");
-            }
+/// An attribute procedural macro for an inline module or an impl block holding the methods of a program.
+/// Every public function becomes a method, and a single `dispatch(method_id, args_ptr, args_len)` function is
+/// exported in place of one export per method. The method id of a method is the FNV-1a hash of its name, see
+/// `wasmlanche_sdk::program::method_id`, so methods can be added or reordered without changing the ids of others.
+/// Arguments are decoded for each method as `#[expose]` does, and results handed to the host with `set_call_result`.
+///
+/// One method may be marked `#[init]`. Its first parameter must be a `ProgramContext`, which is not the context the
+/// caller passes: dispatch creates the storage of a new program and passes its context instead. Both the called program
+/// and the new one are marked initialized, and dispatch rejects init on an initialized program with
+/// `INVOKE_ALREADY_INITIALIZED`, so init runs exactly once for each program. Dispatch returns the id of the new program
+/// when calling init. Methods returning a `Result` discard
+/// their state changes when they return an `Err`, as exposed functions do.
+#[proc_macro_attribute]
+pub fn program(_: TokenStream, item: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(item as Item);
+    match program::program_impl(input.clone()) {
+        Ok(output) => output.into(),
+        Err(err) => {
+            // Keep the original items, without the #[init] markers only #[program] understands.
+            let input = program::strip_init_attrs(input);
+            let err = err.to_compile_error();
+            quote!(#input #err).into()
         }
-        panic!("Unsupported function parameter format.");
-    });
+    }
+}
 
-    // Collect all parameter names and types into separate vectors.
-    let param_names: Vec<_> = full_params.clone().map(|(name, _)| name).collect();
-    // Copy the iterator so we can use it again.
-    let param_names_cloned: Vec<_> = param_names.clone();
-    let param_types: Vec<_> = full_params.map(|(_, ty)| ty).collect();
+/// Marks the constructor of a `#[program]`. It has no meaning on its own.
+#[proc_macro_attribute]
+pub fn init(_: TokenStream, item: TokenStream) -> TokenStream {
+    let err = Error::new(
+        proc_macro2::Span::call_site(),
+        "#[init] can only be used on a public function of a #[program] module or impl block",
+    )
+    .to_compile_error();
+    let item = TokenStream2::from(item);
+    quote!(#item #err).into()
+}
 
-    // Extract the original function's return type. This must be a WASM supported type.
-    let return_type = &input.sig.output;
-    let output = quote! {
+fn expose_impl(input: &ItemFn) -> Result<TokenStream2, Error> {
+    check_signature(&input.sig)?;
+
+    let name = &input.sig.ident;
+    let new_name = Ident::new(&format!("{}_guest", name), name.span()); // Create a new name for the generated function(name that will be called by the host)
+
+    // Each parameter is decoded into its own binding, which leaves any pattern to the original function.
+    let param_types: Vec<_> = typed_params(&input.sig)
+        .map(|pat_type| &pat_type.ty)
+        .collect();
+    let param_names: Vec<_> = (0..param_types.len())
+        .map(|index| format_ident!("arg_{}", index))
+        .collect();
+
+    // Only WASM supported primitives can be returned across the boundary, any other result
+    // is only passed through the host's return buffer.
+    let (return_type, return_value, invalid_return) = match &input.sig.output {
+        ReturnType::Type(_, ty) if is_supported_primitive(ty) => (
+            quote!(-> #ty),
+            quote!(result),
+            quote!(<#ty as ::std::default::Default>::default()),
+        ),
+        _ => (quote!(), quote!(), quote!()),
+    };
+    let call = atomic_call(&input.sig, quote!(#name(#(#param_names),*)));
+    let abi = abi_entry(&input.attrs, &input.sig, &new_name.to_string(), None, 0);
+    Ok(quote! {
         // Need to include the original function in the output, so contract can call itself
         #input
-        #[no_mangle]
-        pub extern "C" fn #new_name(#(#param_names: #param_types), *) #return_type {
-            // .into() uses the From() on each argument in the iterator to convert it to the type we want. 70% sure about this statement.
-            #name(#(#param_names_cloned.into()),*) // This means that every parameter type must implement From<i64>(except for the supported primitive types).
+        #abi
+        /// # Safety
+        /// Only called by the host, with an argument buffer it allocated in this module and hands over.
+        // only the runtime looks exports up by name, so native builds of programs can link together
+        #[cfg_attr(target_family = "wasm", no_mangle)]
+        pub unsafe extern "C" fn #new_name(args_ptr: *mut u8, args_len: usize) #return_type {
+            let mut args = ::wasmlanche_sdk::program::CallArgs::from_host(args_ptr, args_len);
+            let decoded = (|| {
+                #(let #param_names = args.next_arg::<#param_types>()?;)*
+                args.finish()?;
+                Ok::<_, ::wasmlanche_sdk::errors::StorageError>((#(#param_names,)*))
+            })();
+            let Ok((#(#param_names,)*)) = decoded else {
+                ::wasmlanche_sdk::host::set_call_error(::wasmlanche_sdk::host::INVOKE_INVALID_ARGUMENTS);
+                return #invalid_return;
+            };
+            let result = #call;
+            // Hand the tagged result to the host so calling programs can decode it, which requires the return type to implement Store.
+            ::wasmlanche_sdk::host::set_call_result(&result);
+            #return_value
         }
-    };
-    TokenStream::from(output)
+    })
 }
 
-/// Returns whether the type_path represents a supported primitive type.
-fn is_supported_primitive(type_path: &std::boxed::Box<Type>) -> bool {
-    if let Type::Path(ref type_path) = **type_path {
-        let ident = &type_path.path.segments[0].ident;
-        let ident_str = ident.to_string();
-        matches!(ident_str.as_str(), "i32" | "i64" | "bool")
+/// Returns [call] of the function with [sig], run atomically if the function returns a `Result`
+/// so its state changes are discarded when it returns an `Err`.
+pub(crate) fn atomic_call(sig: &Signature, call: TokenStream2) -> TokenStream2 {
+    let returns_result = match &sig.output {
+        ReturnType::Type(_, ty) => match &**ty {
+            Type::Path(TypePath { qself: None, path }) => path
+                .segments
+                .last()
+                .is_some_and(|segment| segment.ident == "Result"),
+            _ => false,
+        },
+        ReturnType::Default => false,
+    };
+    if returns_result {
+        quote!(::wasmlanche_sdk::store::atomic(|| #call))
     } else {
-        false
+        call
     }
 }
+
+/// Returns a static placing the ABI of the function with [sig] and [attrs] in the ABI custom section.
+/// [export] is the function the host calls, and [selector] the method id it passes to it, if any.
+/// The first [supplied] parameters are not passed by callers. The linker concatenates the statics
+/// of every exposed function, so each entry is a single line of JSON.
+pub(crate) fn abi_entry(
+    attrs: &[Attribute],
+    sig: &Signature,
+    export: &str,
+    selector: Option<u32>,
+    supplied: usize,
+) -> TokenStream2 {
+    let params: Vec<_> = typed_params(sig)
+        .enumerate()
+        .skip(supplied)
+        .map(|(index, pat_type)| {
+            // destructured parameters have no name of their own
+            let name = match &*pat_type.pat {
+                Pat::Ident(pat_ident) => pat_ident.ident.to_string(),
+                _ => format!("arg_{}", index),
+            };
+            json!({ "name": name, "type": type_name(&pat_type.ty) })
+        })
+        .collect();
+    let returns = match &sig.output {
+        ReturnType::Default => "()".to_string(),
+        ReturnType::Type(_, ty) => type_name(ty),
+    };
+    let docs: Vec<_> = attrs
+        .iter()
+        .filter_map(|attr| match attr.parse_meta() {
+            Ok(Meta::NameValue(nv)) if nv.path.is_ident("doc") => match nv.lit {
+                Lit::Str(doc) => Some(doc.value().trim().to_string()),
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect();
+
+    let mut entry = json!({
+        "name": sig.ident.to_string(),
+        "export": export,
+        "params": params,
+        "returns": returns,
+        "docs": docs.join("\n"),
+    });
+    if let Some(selector) = selector {
+        entry["selector"] = json!(selector);
+    }
+    let bytes = format!("{}\n", entry).into_bytes();
+    let len = bytes.len();
+    let bytes = Literal::byte_string(&bytes);
+    // an unnamed scope per entry, as names derived from the function could collide
+    quote! {
+        const _: () = {
+            #[used]
+            #[cfg_attr(target_family = "wasm", link_section = #ABI_SECTION)]
+            static ABI: [u8; #len] = *#bytes;
+        };
+    }
+}
+
+/// Returns the typed parameters of [sig], skipping any receiver.
+pub(crate) fn typed_params(sig: &Signature) -> impl Iterator<Item = &PatType> {
+    sig.inputs.iter().filter_map(|fn_arg| match fn_arg {
+        FnArg::Typed(pat_type) => Some(pat_type),
+        FnArg::Receiver(_) => None,
+    })
+}
+
+/// Returns the name of [ty] as written in the source, without whitespace.
+pub(crate) fn type_name(ty: &Type) -> String {
+    ty.to_token_stream()
+        .to_string()
+        .split_whitespace()
+        .collect()
+}
+
+/// Returns an error for every part of the signature that can not be exposed to the host.
+pub(crate) fn check_signature(sig: &Signature) -> Result<(), Error> {
+    let mut errors = Vec::new();
+
+    if let Some(asyncness) = &sig.asyncness {
+        errors.push(Error::new_spanned(
+            asyncness,
+            "exposed functions can not be `async`, the host calls them synchronously",
+        ));
+    }
+    if !sig.generics.params.is_empty() {
+        errors.push(Error::new_spanned(
+            &sig.generics.params,
+            "exposed functions can not be generic, the host needs concrete parameter types; \
+             expose a function for each type instead",
+        ));
+    }
+    for fn_arg in &sig.inputs {
+        match fn_arg {
+            FnArg::Receiver(receiver) => errors.push(Error::new_spanned(
+                receiver,
+                "exposed functions can not take `self`; expose a free function that takes the \
+                 value as a parameter instead",
+            )),
+            FnArg::Typed(pat_type) => errors.extend(check_type(&pat_type.ty, "parameters")),
+        }
+    }
+    if let ReturnType::Type(_, ty) = &sig.output {
+        errors.extend(check_type(ty, "return types"));
+    }
+
+    let mut errors = errors.into_iter();
+    match errors.next() {
+        Some(mut error) => {
+            errors.for_each(|other| error.combine(other));
+            Err(error)
+        }
+        None => Ok(()),
+    }
+}
+
+/// Returns an error if [ty] can not be passed through the host. [position] names where the type is used.
+fn check_type(ty: &Type, position: &str) -> Option<Error> {
+    match ty {
+        Type::Reference(reference) => Some(Error::new_spanned(
+            reference,
+            format!(
+                "exposed function {position} must be owned types implementing `Store`, \
+                 e.g. `String` instead of `&str`"
+            ),
+        )),
+        Type::ImplTrait(impl_trait) => Some(Error::new_spanned(
+            impl_trait,
+            format!("exposed function {position} must be concrete types implementing `Store`"),
+        )),
+        _ => None,
+    }
+}
+
+/// Returns whether [ty] names a WASM supported primitive, either directly or through
+/// its full path such as `std::primitive::i64`.
+pub(crate) fn is_supported_primitive(ty: &Type) -> bool {
+    let Type::Path(TypePath { qself: None, path }) = ty else {
+        return false;
+    };
+    if path
+        .segments
+        .iter()
+        .any(|segment| !matches!(segment.arguments, PathArguments::None))
+    {
+        return false;
+    }
+    let segments: Vec<_> = path
+        .segments
+        .iter()
+        .map(|segment| segment.ident.to_string())
+        .collect();
+    let primitive = match segments.as_slice() {
+        [primitive] if path.leading_colon.is_none() => primitive,
+        [krate, module, primitive]
+            if (krate == "std" || krate == "core") && module == "primitive" =>
+        {
+            primitive
+        }
+        _ => return false,
+    };
+    matches!(primitive.as_str(), "i32" | "i64" | "u64" | "bool")
+}
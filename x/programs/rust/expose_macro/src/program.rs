@@ -0,0 +1,304 @@
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use syn::{
+    Attribute, Error, ImplItem, Item, ItemImpl, ItemMod, Signature, Type, TypePath, Visibility,
+};
+
+use crate::{abi_entry, atomic_call, check_signature, typed_params};
+
+/// The name of the function the host calls for every method of a program.
+const DISPATCH_FN: &str = "dispatch";
+
+/// A method of a program and the path the generated dispatch function calls it with.
+struct Method {
+    attrs: Vec<Attribute>,
+    sig: Signature,
+    call: TokenStream2,
+    init: bool,
+}
+
+pub(crate) fn program_impl(mut input: Item) -> Result<TokenStream2, Error> {
+    match &mut input {
+        Item::Mod(item_mod) => {
+            let methods = module_methods(item_mod)?;
+            let generated = dispatch(&methods)?;
+            // The dispatch function is placed in the module so the methods resolve.
+            let (_, items) = item_mod
+                .content
+                .as_mut()
+                .expect("checked by module_methods");
+            items.push(Item::Verbatim(generated));
+            Ok(quote!(#input))
+        }
+        Item::Impl(item_impl) => {
+            let methods = impl_methods(item_impl)?;
+            let generated = dispatch(&methods)?;
+            Ok(quote!(#input #generated))
+        }
+        other => Err(Error::new_spanned(
+            other,
+            "#[program] must be used on an inline module or an impl block",
+        )),
+    }
+}
+
+/// Removes any `#[init]` attribute from [attrs] and returns whether there was one.
+pub(crate) fn take_init_attr(attrs: &mut Vec<Attribute>) -> bool {
+    let len = attrs.len();
+    attrs.retain(|attr| !attr.path.is_ident("init"));
+    attrs.len() != len
+}
+
+/// Removes every `#[init]` attribute from the functions of [input].
+pub(crate) fn strip_init_attrs(mut input: Item) -> Item {
+    match &mut input {
+        Item::Mod(ItemMod {
+            content: Some((_, items)),
+            ..
+        }) => items.iter_mut().for_each(|item| {
+            if let Item::Fn(item_fn) = item {
+                take_init_attr(&mut item_fn.attrs);
+            }
+        }),
+        Item::Impl(item_impl) => item_impl.items.iter_mut().for_each(|item| {
+            if let ImplItem::Method(method) = item {
+                take_init_attr(&mut method.attrs);
+            }
+        }),
+        _ => (),
+    }
+    input
+}
+
+/// Returns the public functions of [item_mod], which are the methods of the program.
+fn module_methods(item_mod: &mut ItemMod) -> Result<Vec<Method>, Error> {
+    let Some((_, items)) = item_mod.content.as_mut() else {
+        return Err(Error::new_spanned(
+            &item_mod.ident,
+            "#[program] modules must be inline, e.g. `mod counter { ... }`",
+        ));
+    };
+    let mut methods = Vec::new();
+    for item in items {
+        let Item::Fn(item_fn) = item else {
+            continue;
+        };
+        let init = take_init_attr(&mut item_fn.attrs);
+        if !matches!(item_fn.vis, Visibility::Public(_)) {
+            if init {
+                return Err(Error::new_spanned(
+                    &item_fn.sig,
+                    "the #[init] function must be `pub`",
+                ));
+            }
+            continue;
+        }
+        let ident = &item_fn.sig.ident;
+        methods.push(Method {
+            attrs: item_fn.attrs.clone(),
+            sig: item_fn.sig.clone(),
+            call: quote!(#ident),
+            init,
+        });
+    }
+    Ok(methods)
+}
+
+/// Returns the public associated functions of [item_impl], which are the methods of the program.
+fn impl_methods(item_impl: &mut ItemImpl) -> Result<Vec<Method>, Error> {
+    if !item_impl.generics.params.is_empty() {
+        return Err(Error::new_spanned(
+            &item_impl.generics,
+            "#[program] impl blocks can not be generic",
+        ));
+    }
+    if let Some((_, path, _)) = &item_impl.trait_ {
+        return Err(Error::new_spanned(
+            path,
+            "#[program] must be used on an inherent impl block, not a trait impl",
+        ));
+    }
+    let self_ty = &item_impl.self_ty;
+    let mut methods = Vec::new();
+    for item in &mut item_impl.items {
+        let ImplItem::Method(method) = item else {
+            continue;
+        };
+        let init = take_init_attr(&mut method.attrs);
+        if !matches!(method.vis, Visibility::Public(_)) {
+            if init {
+                return Err(Error::new_spanned(
+                    &method.sig,
+                    "the #[init] function must be `pub`",
+                ));
+            }
+            continue;
+        }
+        let ident = &method.sig.ident;
+        methods.push(Method {
+            attrs: method.attrs.clone(),
+            sig: method.sig.clone(),
+            call: quote!(<#self_ty>::#ident),
+            init,
+        });
+    }
+    Ok(methods)
+}
+
+/// Returns the dispatch function calling each of [methods] by its method id, along with their ABI.
+fn dispatch(methods: &[Method]) -> Result<TokenStream2, Error> {
+    let mut arms = Vec::new();
+    let mut abi = Vec::new();
+    let mut ids: Vec<(u32, &Signature)> = Vec::new();
+    let mut init: Option<&Signature> = None;
+
+    for method in methods {
+        check_signature(&method.sig)?;
+        let name = method.sig.ident.to_string();
+        let id = method_id(&name);
+        if let Some((_, other)) = ids.iter().find(|(other_id, _)| *other_id == id) {
+            return Err(Error::new_spanned(
+                &method.sig.ident,
+                format!(
+                    "the method id of `{}` collides with `{}`, rename one of them",
+                    name, other.ident
+                ),
+            ));
+        }
+        ids.push((id, &method.sig));
+
+        let param_types: Vec<_> = typed_params(&method.sig)
+            .map(|pat_type| &pat_type.ty)
+            .collect();
+        if method.init {
+            if let Some(other) = init {
+                return Err(Error::new_spanned(
+                    &method.sig.ident,
+                    format!("`{}` is already the #[init] function", other.ident),
+                ));
+            }
+            init = Some(&method.sig);
+            if !param_types.first().is_some_and(|ty| is_program_context(ty)) {
+                return Err(Error::new_spanned(
+                    &method.sig,
+                    "the #[init] function must take the new program's `ProgramContext` as its \
+                     first parameter",
+                ));
+            }
+        }
+        // The context of a new program is passed to init by the dispatch function.
+        let supplied = usize::from(method.init);
+        let param_names: Vec<_> = (0..param_types.len())
+            .map(|index| format_ident!("arg_{}", index))
+            .collect();
+        let decoded_names = &param_names[supplied..];
+        let decoded_types = &param_types[supplied..];
+        let call = &method.call;
+        let atomic = atomic_call(&method.sig, quote!(#call(#(#param_names),*)));
+
+        let decode = quote! {
+            let decoded = (|| {
+                #(let #decoded_names = args.next_arg::<#decoded_types>()?;)*
+                args.finish()?;
+                Ok::<_, ::wasmlanche_sdk::errors::StorageError>((#(#decoded_names,)*))
+            })();
+            let Ok((#(#decoded_names,)*)) = decoded else {
+                ::wasmlanche_sdk::host::set_call_error(::wasmlanche_sdk::host::INVOKE_INVALID_ARGUMENTS);
+                return ::wasmlanche_sdk::host::INVOKE_INVALID_ARGUMENTS;
+            };
+        };
+        let arm = if method.init {
+            let ctx = &param_names[0];
+            quote! {
+                #id => {
+                    // The caller passes the context of the called program ahead of the arguments.
+                    let Ok(called) = args.next_arg::<::wasmlanche_sdk::store::ProgramContext>() else {
+                        ::wasmlanche_sdk::host::set_call_error(::wasmlanche_sdk::host::INVOKE_INVALID_ARGUMENTS);
+                        return ::wasmlanche_sdk::host::INVOKE_INVALID_ARGUMENTS;
+                    };
+                    #decode
+                    // Neither the called program nor the one created here can run init again.
+                    let #ctx = match ::wasmlanche_sdk::program::mark_initialized(&called) {
+                        Ok(true) => ::wasmlanche_sdk::host::init_program_storage(),
+                        Ok(false) => {
+                            ::wasmlanche_sdk::host::set_call_error(::wasmlanche_sdk::host::INVOKE_ALREADY_INITIALIZED);
+                            return ::wasmlanche_sdk::host::INVOKE_ALREADY_INITIALIZED;
+                        }
+                        Err(_) => {
+                            ::wasmlanche_sdk::host::set_call_error(::wasmlanche_sdk::host::INVOKE_TRAP);
+                            return ::wasmlanche_sdk::host::INVOKE_TRAP;
+                        }
+                    };
+                    if ::wasmlanche_sdk::program::mark_initialized(&#ctx).is_err() {
+                        ::wasmlanche_sdk::host::set_call_error(::wasmlanche_sdk::host::INVOKE_TRAP);
+                        return ::wasmlanche_sdk::host::INVOKE_TRAP;
+                    }
+                    // Callers read the new program from the result, the host from the status.
+                    let program = #ctx.clone();
+                    let result = #atomic;
+                    let program_id = program.program_id as i64;
+                    ::wasmlanche_sdk::host::set_call_result(&::wasmlanche_sdk::program::Initialized { program, result });
+                    program_id
+                }
+            }
+        } else {
+            quote! {
+                #id => {
+                    #decode
+                    let result = #atomic;
+                    ::wasmlanche_sdk::host::set_call_result(&result);
+                    0
+                }
+            }
+        };
+        arms.push(arm);
+        abi.push(abi_entry(
+            &method.attrs,
+            &method.sig,
+            DISPATCH_FN,
+            Some(id),
+            supplied,
+        ));
+    }
+
+    let dispatch_fn = format_ident!("{}", DISPATCH_FN);
+    Ok(quote! {
+        #(#abi)*
+        /// Calls the method of the program identified by [method_id] with the arguments in the buffer
+        /// at [args_ptr]. Returns the id of the new program when calling the #[init] function, 0 when
+        /// calling any other method, or a negative status if the call failed.
+        ///
+        /// # Safety
+        /// Only called by the host, with an argument buffer it allocated in this module and hands over.
+        // only the runtime looks exports up by name, so native builds of programs can link together
+        #[cfg_attr(target_family = "wasm", no_mangle)]
+        pub unsafe extern "C" fn #dispatch_fn(method_id: u32, args_ptr: *mut u8, args_len: usize) -> i64 {
+            let mut args = ::wasmlanche_sdk::program::CallArgs::from_host(args_ptr, args_len);
+            match method_id {
+                #(#arms)*
+                _ => {
+                    ::wasmlanche_sdk::host::set_call_error(::wasmlanche_sdk::host::INVOKE_UNKNOWN_METHOD);
+                    ::wasmlanche_sdk::host::INVOKE_UNKNOWN_METHOD
+                }
+            }
+        }
+    })
+}
+
+/// Returns the method id of [name], matching `wasmlanche_sdk::program::method_id`.
+fn method_id(name: &str) -> u32 {
+    name.bytes().fold(0x811c_9dc5, |hash: u32, byte| {
+        (hash ^ u32::from(byte)).wrapping_mul(0x0100_0193)
+    })
+}
+
+/// Returns whether [ty] names `ProgramContext`.
+fn is_program_context(ty: &Type) -> bool {
+    match ty {
+        Type::Path(TypePath { qself: None, path }) => path
+            .segments
+            .last()
+            .is_some_and(|segment| segment.ident == "ProgramContext"),
+        _ => false,
+    }
+}
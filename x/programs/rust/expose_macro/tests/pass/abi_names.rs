@@ -0,0 +1,23 @@
+use expose_macro::{expose, program};
+
+// the abi entries of functions named alike must not collide
+
+#[expose]
+pub fn get() -> i64 {
+    1
+}
+
+#[expose]
+#[allow(non_snake_case)]
+pub fn GET() -> i64 {
+    2
+}
+
+#[program]
+mod counter {
+    pub fn get() -> i64 {
+        3
+    }
+}
+
+fn main() {}
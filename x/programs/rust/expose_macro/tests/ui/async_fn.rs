@@ -0,0 +1,8 @@
+use expose_macro::expose;
+
+#[expose]
+pub async fn get() -> i64 {
+    0
+}
+
+fn main() {}
@@ -0,0 +1,8 @@
+use expose_macro::expose;
+
+#[expose]
+pub fn get<T>(value: T) -> T {
+    value
+}
+
+fn main() {}
@@ -0,0 +1,8 @@
+use expose_macro::expose;
+
+#[expose]
+pub fn total(values: impl Iterator<Item = i64>) -> i64 {
+    values.sum()
+}
+
+fn main() {}
@@ -0,0 +1,11 @@
+use expose_macro::program;
+
+#[program]
+mod counter {
+    #[init]
+    pub fn init(start: i64) -> i64 {
+        start
+    }
+}
+
+fn main() {}
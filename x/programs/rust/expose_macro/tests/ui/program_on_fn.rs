@@ -0,0 +1,8 @@
+use expose_macro::program;
+
+#[program]
+pub fn init() -> i64 {
+    0
+}
+
+fn main() {}
@@ -0,0 +1,8 @@
+use expose_macro::expose;
+
+#[expose]
+pub fn len(name: &str) -> i64 {
+    name.len() as i64
+}
+
+fn main() {}
@@ -0,0 +1,8 @@
+use expose_macro::expose;
+
+#[expose]
+pub fn name() -> &'static str {
+    "name"
+}
+
+fn main() {}
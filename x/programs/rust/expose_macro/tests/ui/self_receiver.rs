@@ -0,0 +1,12 @@
+use expose_macro::expose;
+
+struct Counter;
+
+impl Counter {
+    #[expose]
+    pub fn get(&self) -> i64 {
+        0
+    }
+}
+
+fn main() {}
@@ -0,0 +1,290 @@
+extern crate proc_macro;
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use syn::{
+    parse_macro_input, parse_quote, Attribute, Data, DeriveInput, Error, Fields, Generics, Lit,
+    Meta, NestedMeta,
+};
+
+mod state;
+
+/// A derive procedural macro that implements `wasmlanche_sdk::store::Store` for a struct or enum.
+/// Every field must itself implement `Store`.
+///
+/// The generated layout starts with the type's version byte, which defaults to 0 and can be
+/// set with `#[storable(version = N)]`. Enums then write the index of their variant as a single
+/// byte. Finally each field is written in declaration order as a big endian u32 length
+/// followed by the field's tagged bytes. Bump the version whenever fields are added, removed or
+/// reordered so that values stored with the old layout are rejected instead of misread.
+#[proc_macro_derive(Storable, attributes(storable))]
+pub fn derive_storable(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match storable_impl(input) {
+        Ok(output) => output.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+/// A derive procedural macro that implements `wasmlanche_sdk::event::Event` for a struct, so
+/// programs can emit it with `ProgramContext::emit_event`. Every field must implement `Store`.
+///
+/// The topic defaults to the name of the struct and can be set with `#[event(topic = "...")]`.
+/// The payload uses the layout `Storable` writes for a struct with version 0, so indexers decode
+/// events as they decode stored values.
+#[proc_macro_derive(Event, attributes(event))]
+pub fn derive_event(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match event_impl(input) {
+        Ok(output) => output.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+/// An attribute procedural macro declaring the state of a program as a struct with named fields.
+/// The struct is replaced by a handle holding the program's `ProgramContext`, and each field by an
+/// accessor of the same name and visibility, keyed in storage by the field's name. Fields typed
+/// `StorageMap<K, V>` return the map, any other field `T` returns a `StorageValue<T>`, so values are
+/// only read from the host when used, e.g. `state.total_supply().get()`.
+///
+/// `init(ctx, ..)` stores the initial value of every value field in declaration order, and
+/// `publish(..)` does the same for the storage of a new program. Maps start empty. Fields marked
+/// `#[state(skip)]` are left out of the initializers, for values set after the program is created.
+#[proc_macro_attribute]
+pub fn state(_: TokenStream, item: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(item as DeriveInput);
+    match state::state_impl(input) {
+        Ok(output) => output.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+fn storable_impl(input: DeriveInput) -> Result<TokenStream2, Error> {
+    let name = &input.ident;
+    let version = parse_version(&input.attrs)?;
+    let generics = add_store_bounds(input.generics.clone());
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let (tag, pack, unpack) = match &input.data {
+        Data::Struct(data) => {
+            let (pattern, pack) = pack_fields(&data.fields);
+            let unpack = unpack_fields(quote!(Self), &data.fields);
+            (
+                quote!(::wasmlanche_sdk::store::Tag::STRUCT),
+                quote! {
+                    let Self #pattern = self;
+                    #pack
+                },
+                quote!(#unpack),
+            )
+        }
+        Data::Enum(data) => {
+            if data.variants.len() > usize::from(u8::MAX) + 1 {
+                return Err(Error::new_spanned(
+                    &input.ident,
+                    "Storable enums are limited to 256 variants.",
+                ));
+            }
+            let mut pack_arms = Vec::new();
+            let mut unpack_arms = Vec::new();
+            for (index, variant) in data.variants.iter().enumerate() {
+                let index = index as u8;
+                let ident = &variant.ident;
+                let (pattern, pack) = pack_fields(&variant.fields);
+                let unpack = unpack_fields(quote!(Self::#ident), &variant.fields);
+                pack_arms.push(quote! {
+                    Self::#ident #pattern => {
+                        packer.pack_u8(#index);
+                        #pack
+                    }
+                });
+                unpack_arms.push(quote!(#index => #unpack,));
+            }
+            (
+                quote!(::wasmlanche_sdk::store::Tag::ENUM),
+                quote! {
+                    match self {
+                        #(#pack_arms)*
+                    }
+                },
+                quote! {
+                    match unpacker.unpack_u8()? {
+                        #(#unpack_arms)*
+                        variant => {
+                            return Err(::wasmlanche_sdk::errors::StorageError::InvalidVariant(variant));
+                        }
+                    }
+                },
+            )
+        }
+        Data::Union(data) => {
+            return Err(Error::new_spanned(
+                data.union_token,
+                "Storable cannot be derived for unions.",
+            ));
+        }
+    };
+
+    Ok(quote! {
+        impl #impl_generics ::wasmlanche_sdk::store::Store for #name #ty_generics #where_clause {
+            fn as_bytes(&self) -> ::std::borrow::Cow<'_, [u8]> {
+                let mut packer = ::wasmlanche_sdk::codec::Packer::new(#version);
+                #pack
+                ::std::borrow::Cow::Owned(packer.finish())
+            }
+
+            fn as_tag(&self) -> ::wasmlanche_sdk::store::Tag {
+                #tag
+            }
+
+            fn from_bytes(bytes: &[u8]) -> Result<Self, ::wasmlanche_sdk::errors::StorageError> {
+                let bytes = #tag.strip(bytes)?;
+                let mut unpacker = ::wasmlanche_sdk::codec::Unpacker::new(bytes, #version)?;
+                let value = #unpack;
+                unpacker.finish()?;
+                Ok(value)
+            }
+        }
+    })
+}
+
+fn event_impl(input: DeriveInput) -> Result<TokenStream2, Error> {
+    let name = &input.ident;
+    let topic = parse_topic(&input.attrs)?.unwrap_or_else(|| name.to_string());
+    let generics = add_store_bounds(input.generics.clone());
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let Data::Struct(data) = &input.data else {
+        return Err(Error::new_spanned(
+            &input.ident,
+            "Event can only be derived for structs.",
+        ));
+    };
+    let (pattern, pack) = pack_fields(&data.fields);
+    let unpack = unpack_fields(quote!(Self), &data.fields);
+
+    Ok(quote! {
+        impl #impl_generics ::wasmlanche_sdk::event::Event for #name #ty_generics #where_clause {
+            const TOPIC: &'static str = #topic;
+
+            fn payload(&self) -> Vec<u8> {
+                let mut packer = ::wasmlanche_sdk::codec::Packer::new(0);
+                let Self #pattern = self;
+                #pack
+                packer.finish()
+            }
+
+            fn from_payload(payload: &[u8]) -> Result<Self, ::wasmlanche_sdk::errors::StorageError> {
+                let mut unpacker = ::wasmlanche_sdk::codec::Unpacker::new(payload, 0)?;
+                let value = #unpack;
+                unpacker.finish()?;
+                Ok(value)
+            }
+        }
+    })
+}
+
+/// Returns the topic set by `#[event(topic = "...")]`, if any.
+fn parse_topic(attrs: &[Attribute]) -> Result<Option<String>, Error> {
+    match parse_attr_value(attrs, "event", "topic", "\"...\"")? {
+        Some(Lit::Str(lit)) => Ok(Some(lit.value())),
+        Some(lit) => Err(Error::new_spanned(lit, "topic must be a string literal")),
+        None => Ok(None),
+    }
+}
+
+/// Returns the version set by `#[storable(version = N)]`, or 0 if none is set.
+fn parse_version(attrs: &[Attribute]) -> Result<u8, Error> {
+    match parse_attr_value(attrs, "storable", "version", "N")? {
+        Some(Lit::Int(lit)) => lit.base10_parse::<u8>(),
+        Some(lit) => Err(Error::new_spanned(lit, "version must be a u8 literal")),
+        None => Ok(0),
+    }
+}
+
+/// Returns the literal set by `#[attr(key = value)]` in [attrs], failing if [key] is set more
+/// than once or on any other argument of [attr]. [example] is the value shown in errors.
+fn parse_attr_value(
+    attrs: &[Attribute],
+    attr: &str,
+    key: &str,
+    example: &str,
+) -> Result<Option<Lit>, Error> {
+    let mut value = None;
+    for attribute in attrs
+        .iter()
+        .filter(|attribute| attribute.path.is_ident(attr))
+    {
+        let Meta::List(list) = attribute.parse_meta()? else {
+            return Err(Error::new_spanned(
+                attribute,
+                format!("expected #[{attr}({key} = {example})]"),
+            ));
+        };
+        for nested in list.nested {
+            match nested {
+                NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident(key) => {
+                    if value.is_some() {
+                        return Err(Error::new_spanned(
+                            nv,
+                            format!("duplicate {attr} attribute `{key}`"),
+                        ));
+                    }
+                    value = Some(nv.lit);
+                }
+                _ => {
+                    return Err(Error::new_spanned(
+                        nested,
+                        format!("unknown {attr} attribute, expected `{key} = {example}`"),
+                    ));
+                }
+            }
+        }
+    }
+    Ok(value)
+}
+
+/// Requires every type parameter to implement Store.
+fn add_store_bounds(mut generics: Generics) -> Generics {
+    for param in generics.type_params_mut() {
+        param
+            .bounds
+            .push(parse_quote!(::wasmlanche_sdk::store::Store));
+    }
+    generics
+}
+
+/// Returns a pattern binding every field by reference and the statements packing them in order.
+fn pack_fields(fields: &Fields) -> (TokenStream2, TokenStream2) {
+    let bindings: Vec<_> = fields
+        .iter()
+        .enumerate()
+        .map(|(index, _)| format_ident!("field_{}", index))
+        .collect();
+    let pattern = match fields {
+        Fields::Named(named) => {
+            let names = named.named.iter().map(|field| &field.ident);
+            quote!({ #(#names: #bindings),* })
+        }
+        Fields::Unnamed(_) => quote!(( #(#bindings),* )),
+        Fields::Unit => quote!(),
+    };
+    let pack = quote!(#(packer.pack(#bindings);)*);
+    (pattern, pack)
+}
+
+/// Returns an expression constructing [path] from fields unpacked in declaration order.
+fn unpack_fields(path: TokenStream2, fields: &Fields) -> TokenStream2 {
+    match fields {
+        Fields::Named(named) => {
+            let names = named.named.iter().map(|field| &field.ident);
+            quote!(#path { #(#names: unpacker.unpack()?),* })
+        }
+        Fields::Unnamed(unnamed) => {
+            let values = unnamed.unnamed.iter().map(|_| quote!(unpacker.unpack()?));
+            quote!(#path( #(#values),* ))
+        }
+        Fields::Unit => quote!(#path),
+    }
+}
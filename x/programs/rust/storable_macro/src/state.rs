@@ -0,0 +1,179 @@
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{Attribute, Data, DeriveInput, Error, Fields, Ident, Meta, NestedMeta, Type, TypePath};
+
+/// Names of the functions generated for every state, which fields can not reuse.
+const RESERVED: [&str; 4] = ["new", "ctx", "init", "publish"];
+
+/// A field of the state and how it is initialized.
+struct Field {
+    attrs: Vec<Attribute>,
+    vis: syn::Visibility,
+    ident: Ident,
+    ty: Type,
+    map: bool,
+    skip: bool,
+}
+
+pub(crate) fn state_impl(input: DeriveInput) -> Result<TokenStream2, Error> {
+    if !input.generics.params.is_empty() {
+        return Err(Error::new_spanned(
+            &input.generics,
+            "#[state] structs can not be generic",
+        ));
+    }
+    let Data::Struct(data) = &input.data else {
+        return Err(Error::new_spanned(
+            &input.ident,
+            "#[state] can only be used on a struct with named fields",
+        ));
+    };
+    let Fields::Named(named) = &data.fields else {
+        return Err(Error::new_spanned(
+            &input.ident,
+            "#[state] can only be used on a struct with named fields",
+        ));
+    };
+
+    let mut fields = Vec::new();
+    for field in &named.named {
+        let ident = field.ident.clone().expect("named field");
+        if RESERVED.iter().any(|reserved| ident == reserved) {
+            return Err(Error::new_spanned(
+                &ident,
+                format!("`{}` is reserved by #[state], rename the field", ident),
+            ));
+        }
+        let mut attrs = Vec::new();
+        let mut skip = false;
+        for attr in &field.attrs {
+            if attr.path.is_ident("state") {
+                skip |= parse_skip(attr)?;
+            } else {
+                attrs.push(attr.clone());
+            }
+        }
+        let map = is_storage_map(&field.ty);
+        if map && skip {
+            return Err(Error::new_spanned(
+                &ident,
+                "maps are never initialized, `#[state(skip)]` only applies to values",
+            ));
+        }
+        fields.push(Field {
+            attrs,
+            vis: field.vis.clone(),
+            ident,
+            ty: field.ty.clone(),
+            map,
+            skip,
+        });
+    }
+
+    let accessors = fields.iter().map(|field| {
+        let Field {
+            attrs,
+            vis,
+            ident,
+            ty,
+            map,
+            ..
+        } = field;
+        let key = ident.to_string();
+        if *map {
+            quote! {
+                #(#attrs)*
+                #vis fn #ident(&self) -> #ty {
+                    ::wasmlanche_sdk::collections::StorageMap::new(self.ctx.clone(), #key)
+                }
+            }
+        } else {
+            quote! {
+                #(#attrs)*
+                #vis fn #ident(&self) -> ::wasmlanche_sdk::collections::StorageValue<#ty> {
+                    ::wasmlanche_sdk::collections::StorageValue::new(self.ctx.clone(), #key)
+                }
+            }
+        }
+    });
+    let initialized: Vec<_> = fields
+        .iter()
+        .filter(|field| !field.map && !field.skip)
+        .collect();
+    let names: Vec<_> = initialized.iter().map(|field| &field.ident).collect();
+    let types: Vec<_> = initialized.iter().map(|field| &field.ty).collect();
+
+    let DeriveInput {
+        attrs, vis, ident, ..
+    } = &input;
+    Ok(quote! {
+        #(#attrs)*
+        #[derive(Clone)]
+        #vis struct #ident {
+            ctx: ::wasmlanche_sdk::store::ProgramContext,
+        }
+
+        impl #ident {
+            /// Returns the state of the program with [ctx].
+            pub fn new(ctx: ::wasmlanche_sdk::store::ProgramContext) -> Self {
+                Self { ctx }
+            }
+
+            /// Returns the context of the program holding this state.
+            pub fn ctx(&self) -> &::wasmlanche_sdk::store::ProgramContext {
+                &self.ctx
+            }
+
+            /// Stores the initial value of each field in the storage of the program with [ctx].
+            #[allow(clippy::too_many_arguments)]
+            pub fn init(
+                ctx: ::wasmlanche_sdk::store::ProgramContext,
+                #(#names: #types),*
+            ) -> Result<Self, ::wasmlanche_sdk::errors::StorageError> {
+                let state = Self::new(ctx);
+                #(state.#names().set(&#names)?;)*
+                Ok(state)
+            }
+
+            /// Creates the storage of a new program and stores the initial value of each field in it.
+            #[allow(clippy::too_many_arguments)]
+            pub fn publish(
+                #(#names: #types),*
+            ) -> Result<Self, ::wasmlanche_sdk::errors::StorageError> {
+                Self::init(::wasmlanche_sdk::host::init_program_storage(), #(#names),*)
+            }
+
+            #(#accessors)*
+        }
+    })
+}
+
+/// Returns whether [attr] is `#[state(skip)]`, the only field attribute of a state.
+fn parse_skip(attr: &Attribute) -> Result<bool, Error> {
+    let Meta::List(list) = attr.parse_meta()? else {
+        return Err(Error::new_spanned(attr, "expected #[state(skip)]"));
+    };
+    for nested in &list.nested {
+        match nested {
+            NestedMeta::Meta(Meta::Path(path)) if path.is_ident("skip") => {}
+            _ => {
+                return Err(Error::new_spanned(
+                    nested,
+                    "unknown state attribute, expected `skip`",
+                ));
+            }
+        }
+    }
+    Ok(true)
+}
+
+/// Returns whether [ty] names `StorageMap`.
+fn is_storage_map(ty: &Type) -> bool {
+    match ty {
+        Type::Path(TypePath { qself: None, path }) => path
+            .segments
+            .last()
+            .is_some_and(|segment| segment.ident == "StorageMap"),
+        _ => false,
+    }
+}
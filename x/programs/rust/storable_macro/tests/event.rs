@@ -0,0 +1,62 @@
+use storable_macro::Event;
+use wasmlanche_sdk::event::Event;
+use wasmlanche_sdk::host;
+use wasmlanche_sdk::mock;
+
+#[derive(Event, Debug, PartialEq)]
+struct Deposit {
+    amount: u64,
+    memo: String,
+}
+
+#[derive(Event, Debug, PartialEq)]
+#[event(topic = "Withdrawn")]
+struct Withdrawal(u64);
+
+#[derive(Event, Debug, PartialEq)]
+struct Ping;
+
+#[test]
+fn topic_is_name_unless_set() {
+    assert_eq!(Deposit::TOPIC, "Deposit");
+    assert_eq!(Withdrawal::TOPIC, "Withdrawn");
+    assert_eq!(Ping::TOPIC, "Ping");
+}
+
+#[test]
+fn payload_packs_fields_at_version_0() {
+    let deposit = Deposit {
+        amount: 5,
+        memo: "hi".to_string(),
+    };
+    // the version, then each field's length and tagged bytes
+    let expected = [
+        &[0][..],
+        &[0, 0, 0, 9, 12, 0, 0, 0, 0, 0, 0, 0, 5],
+        &[0, 0, 0, 3, 1, b'h', b'i'],
+    ]
+    .concat();
+    assert_eq!(deposit.payload(), expected);
+    assert_eq!(Deposit::from_payload(&expected).unwrap(), deposit);
+    assert_eq!(Ping.payload(), [0]);
+}
+
+#[test]
+fn emits_topic_and_payload() {
+    let ctx = host::init_program_storage();
+    ctx.emit_event(&Withdrawal(7)).unwrap();
+
+    let events = mock::events();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].program_id, ctx.program_id);
+    assert_eq!(events[0].topic, "Withdrawn");
+    assert_eq!(events[0].payload, Withdrawal(7).payload());
+    assert_eq!(events[0].decode::<Withdrawal>(), Some(Withdrawal(7)));
+    assert_eq!(events[0].decode::<Deposit>(), None);
+}
+
+#[test]
+fn rejects_other_payloads() {
+    assert!(Withdrawal::from_payload(&[1, 0, 0, 0, 9, 12, 0, 0, 0, 0, 0, 0, 0, 7]).is_err());
+    assert!(Withdrawal::from_payload(&Ping.payload()).is_err());
+}
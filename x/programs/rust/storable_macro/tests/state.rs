@@ -0,0 +1,72 @@
+use storable_macro::state;
+use wasmlanche_sdk::collections::StorageMap;
+use wasmlanche_sdk::host;
+use wasmlanche_sdk::store::{ProgramContext, RawEntry};
+use wasmlanche_sdk::types::Address;
+
+const ALICE: Address = Address::new([1; 32]);
+
+#[state]
+struct Token {
+    name: String,
+    total_supply: i64,
+    balances: StorageMap<Address, i64>,
+    /// Set after the program is created.
+    #[state(skip)]
+    owner: Address,
+}
+
+/// Returns every raw entry the program [ctx] stores.
+fn entries(ctx: &ProgramContext) -> Vec<RawEntry> {
+    ctx.iter_prefix(&[], &[], &[], 100).unwrap()
+}
+
+#[test]
+fn init_stores_each_value_once() {
+    let ctx = host::init_program_storage();
+    Token::init(ctx.clone(), "coin".to_string(), 10).unwrap();
+    // a single entry for each value, none for the map or the skipped field
+    assert_eq!(entries(&ctx).len(), 2);
+    assert_eq!(
+        ctx.get_value::<String>("name").unwrap(),
+        Some("coin".to_string())
+    );
+    assert_eq!(ctx.get_value::<i64>("total_supply").unwrap(), Some(10));
+
+    let published = Token::publish("other".to_string(), 20).unwrap();
+    assert_ne!(published.ctx().program_id, ctx.program_id);
+    assert_eq!(entries(published.ctx()).len(), 2);
+    assert_eq!(published.total_supply().get().unwrap(), Some(20));
+    // the first program's storage is untouched
+    assert_eq!(ctx.get_value::<i64>("total_supply").unwrap(), Some(10));
+}
+
+#[test]
+fn skipped_fields_are_not_stored() {
+    let token = Token::init(host::init_program_storage(), "coin".to_string(), 10).unwrap();
+    assert!(token.owner().get().unwrap().is_none());
+    assert!(token.ctx().get_value::<Address>("owner").unwrap().is_none());
+
+    token.owner().set(&ALICE).unwrap();
+    assert!(token.ctx().get_value::<Address>("owner").unwrap() == Some(ALICE));
+    assert_eq!(entries(token.ctx()).len(), 3);
+}
+
+#[test]
+fn accessors_read_back_stored_values() {
+    let ctx = host::init_program_storage();
+    let token = Token::init(ctx.clone(), "coin".to_string(), 10).unwrap();
+    token.total_supply().set(&15).unwrap();
+    token.balances().insert(&ALICE, &5).unwrap();
+
+    // a new handle on the same program reads what the first one stored
+    let token = Token::new(ctx.clone());
+    assert_eq!(token.name().get().unwrap(), Some("coin".to_string()));
+    assert_eq!(token.total_supply().get().unwrap(), Some(15));
+    assert_eq!(token.balances().get(&ALICE).unwrap(), Some(5));
+    assert_eq!(ctx.get_value::<i64>("total_supply").unwrap(), Some(15));
+    assert_eq!(
+        ctx.get_map_value::<i64>("balances", ALICE.into()).unwrap(),
+        Some(5)
+    );
+}
@@ -0,0 +1,116 @@
+use storable_macro::Storable;
+use wasmlanche_sdk::errors::StorageError;
+use wasmlanche_sdk::host;
+use wasmlanche_sdk::store::{Store, Tag};
+
+#[derive(Storable, Debug, PartialEq)]
+struct Account {
+    owner: String,
+    balance: u64,
+    frozen: bool,
+}
+
+#[derive(Storable, Debug, PartialEq)]
+struct Pair(i64, Option<u64>);
+
+#[derive(Storable, Debug, PartialEq)]
+struct Empty;
+
+#[derive(Storable, Debug, PartialEq)]
+enum Action {
+    Noop,
+    Transfer { to: String, amount: u64 },
+    Burn(u64),
+}
+
+#[derive(Storable, Debug, PartialEq)]
+struct Wrapper<T> {
+    inner: T,
+}
+
+#[derive(Storable, Debug, PartialEq)]
+#[storable(version = 2)]
+struct Versioned {
+    value: u64,
+}
+
+#[derive(Storable, Debug, PartialEq)]
+struct Unversioned {
+    value: u64,
+}
+
+fn round_trip<T: Store + std::fmt::Debug + PartialEq>(value: T) {
+    let ctx = host::init_program_storage();
+    ctx.store_value("value", &value).unwrap();
+    assert_eq!(ctx.get_value::<T>("value").unwrap(), Some(value));
+}
+
+#[test]
+fn round_trips_through_storage() {
+    round_trip(Account {
+        owner: "alice".to_string(),
+        balance: 42,
+        frozen: true,
+    });
+    round_trip(Pair(-7, Some(3)));
+    round_trip(Pair(7, None));
+    round_trip(Empty);
+    round_trip(Action::Noop);
+    round_trip(Action::Transfer {
+        to: "bob".to_string(),
+        amount: 5,
+    });
+    round_trip(Action::Burn(9));
+    round_trip(Wrapper {
+        inner: Action::Burn(1),
+    });
+    round_trip(Versioned { value: 3 });
+}
+
+#[test]
+fn tags_structs_and_enums() {
+    assert_eq!(Empty.as_tag(), Tag::STRUCT);
+    assert_eq!(Action::Noop.as_tag(), Tag::ENUM);
+    // the version byte, then the variant index
+    assert_eq!(Action::Burn(9).as_bytes()[..2], [0, 2]);
+    assert_eq!(Versioned { value: 3 }.as_bytes()[0], 2);
+}
+
+#[test]
+fn rejects_wrong_tag() {
+    let bytes = Action::Noop.to_tagged_bytes();
+    assert!(matches!(
+        Empty::from_bytes(&bytes),
+        Err(StorageError::TypeMismatch {
+            expected: 7,
+            found: 8
+        })
+    ));
+
+    let ctx = host::init_program_storage();
+    ctx.store_value("value", &5_u64).unwrap();
+    assert!(matches!(
+        ctx.get_value::<Account>("value"),
+        Err(StorageError::TypeMismatch { expected: 7, .. })
+    ));
+}
+
+#[test]
+fn rejects_other_layouts() {
+    let bytes = Unversioned { value: 3 }.to_tagged_bytes();
+    assert!(matches!(
+        Versioned::from_bytes(&bytes),
+        Err(StorageError::InvalidVersion(0))
+    ));
+
+    let mut bytes = Action::Noop.to_tagged_bytes();
+    bytes[2] = 3;
+    assert!(matches!(
+        Action::from_bytes(&bytes),
+        Err(StorageError::InvalidVariant(3))
+    ));
+
+    let mut bytes = Pair(1, None).to_tagged_bytes();
+    bytes.push(0);
+    assert!(Pair::from_bytes(&bytes).is_err());
+}
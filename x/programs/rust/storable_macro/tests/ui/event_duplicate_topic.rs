@@ -0,0 +1,10 @@
+use storable_macro::Event;
+
+#[derive(Event)]
+#[event(topic = "Sent")]
+#[event(topic = "Received")]
+struct Transfer {
+    amount: u64,
+}
+
+fn main() {}
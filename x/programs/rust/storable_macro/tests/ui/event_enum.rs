@@ -0,0 +1,9 @@
+use storable_macro::Event;
+
+#[derive(Event)]
+enum Transfer {
+    Sent(u64),
+    Received(u64),
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use storable_macro::Event;
+
+#[derive(Event)]
+#[event(topic = 1)]
+struct Transfer {
+    amount: u64,
+}
+
+fn main() {}
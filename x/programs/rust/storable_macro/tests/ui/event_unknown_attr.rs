@@ -0,0 +1,9 @@
+use storable_macro::Event;
+
+#[derive(Event)]
+#[event(foo)]
+struct Transfer {
+    amount: u64,
+}
+
+fn main() {}
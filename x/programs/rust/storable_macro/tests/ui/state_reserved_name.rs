@@ -0,0 +1,8 @@
+use storable_macro::state;
+
+#[state]
+struct Token {
+    init: bool,
+}
+
+fn main() {}
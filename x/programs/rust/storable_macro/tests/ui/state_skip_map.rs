@@ -0,0 +1,9 @@
+use storable_macro::state;
+
+#[state]
+struct Token {
+    #[state(skip)]
+    balances: wasmlanche_sdk::collections::StorageMap<u64, u64>,
+}
+
+fn main() {}
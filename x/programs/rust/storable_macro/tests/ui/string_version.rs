@@ -0,0 +1,9 @@
+use storable_macro::Storable;
+
+#[derive(Storable)]
+#[storable(version = "1")]
+struct Balance {
+    amount: u64,
+}
+
+fn main() {}
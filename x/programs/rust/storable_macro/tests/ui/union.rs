@@ -0,0 +1,9 @@
+use storable_macro::Storable;
+
+#[derive(Storable)]
+union Bits {
+    int: u64,
+    float: f64,
+}
+
+fn main() {}
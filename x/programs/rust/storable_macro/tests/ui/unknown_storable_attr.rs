@@ -0,0 +1,9 @@
+use storable_macro::Storable;
+
+#[derive(Storable)]
+#[storable(tag = 1)]
+struct Balance {
+    amount: u64,
+}
+
+fn main() {}
@@ -0,0 +1,94 @@
+//! Generates the host imports from the ABI manifest shared with the Go runtime,
+//! so the module names, function names and signatures linked by the SDK always
+//! match the functions the runtime registers. The crate keeps its own copy of
+//! `x/programs/abi/abi.json`, which `tests/abi.rs` checks is up to date.
+
+use serde_json::Value;
+use std::fmt::Write;
+use std::path::PathBuf;
+use std::{env, fs};
+
+const MANIFEST_PATH: &str = "abi.json";
+
+fn main() {
+    println!("cargo:rerun-if-changed={MANIFEST_PATH}");
+
+    // The mock host replaces the imports, which only exist inside the runtime, on
+    // native targets. WASM builds always link against the real host.
+    println!("cargo:rustc-check-cfg=cfg(mock_host)");
+    let wasm = env::var("CARGO_CFG_TARGET_FAMILY").is_ok_and(|family| family.contains("wasm"));
+    let mock_host = env::var_os("CARGO_FEATURE_MOCK_HOST").is_some() && !wasm;
+    if mock_host {
+        println!("cargo:rustc-cfg=mock_host");
+    }
+
+    let manifest = fs::read_to_string(MANIFEST_PATH).expect("failed to read abi manifest");
+    let manifest: Value = serde_json::from_str(&manifest).expect("failed to parse abi manifest");
+
+    let mut out = String::new();
+    let version = manifest["version"].as_u64().expect("abi version");
+    writeln!(
+        out,
+        "/// The version of the host ABI the SDK was built against."
+    )
+    .unwrap();
+    writeln!(out, "pub const ABI_VERSION: u32 = {version};").unwrap();
+
+    // the mock host defines every function itself
+    let modules = match mock_host {
+        true => &[],
+        false => array(&manifest, "modules").as_slice(),
+    };
+    for module in modules {
+        let module_name = string(module, "name");
+        writeln!(out, "#[link(wasm_import_module = {module_name:?})]").unwrap();
+        writeln!(out, "extern \"C\" {{").unwrap();
+        for function in array(module, "functions") {
+            let name = string(function, "name");
+            let params = array(function, "params")
+                .iter()
+                .map(|param| {
+                    let ty = rust_type(string(param, "type"));
+                    format!("{}: {ty}", string(param, "name"))
+                })
+                .collect::<Vec<_>>()
+                .join(", ");
+            let ret = match array(function, "results").as_slice() {
+                [] => String::new(),
+                [result] => format!(" -> {}", rust_type(result.as_str().expect("result type"))),
+                _ => panic!("{module_name}.{name} returns more than one value"),
+            };
+            writeln!(out, "    #[link_name = {name:?}]").unwrap();
+            writeln!(out, "    fn _{name}({params}){ret};").unwrap();
+        }
+        writeln!(out, "}}").unwrap();
+    }
+
+    let out_dir = PathBuf::from(env::var("OUT_DIR").expect("OUT_DIR is set by cargo"));
+    fs::write(out_dir.join("abi.rs"), out).expect("failed to write abi.rs");
+}
+
+fn array<'a>(value: &'a Value, key: &str) -> &'a Vec<Value> {
+    value[key]
+        .as_array()
+        .unwrap_or_else(|| panic!("abi manifest: `{key}` must be an array"))
+}
+
+fn string<'a>(value: &'a Value, key: &str) -> &'a str {
+    value[key]
+        .as_str()
+        .unwrap_or_else(|| panic!("abi manifest: `{key}` must be a string"))
+}
+
+/// Maps a manifest type to the Rust type passed across the boundary.
+fn rust_type(ty: &str) -> &'static str {
+    match ty {
+        "i32" => "i32",
+        "i64" => "i64",
+        "u32" => "u32",
+        "u64" => "u64",
+        "ptr" => "*const u8",
+        "len" => "usize",
+        _ => panic!("abi manifest: unknown type `{ty}`"),
+    }
+}
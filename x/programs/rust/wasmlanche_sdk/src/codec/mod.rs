@@ -0,0 +1,87 @@
+use crate::errors::StorageError;
+use crate::store::Store;
+
+/// The size in bytes of the length prefix written before each packed value.
+const LEN_PREFIX_SIZE: usize = std::mem::size_of::<u32>();
+
+/// Packer builds the versioned layout used by types deriving `Storable`.
+///
+/// The layout is a single version byte followed by each packed value. Values are
+/// written as a big endian u32 length followed by their tagged bytes, so nested
+/// types can be decoded with [Store::from_bytes].
+pub struct Packer {
+    bytes: Vec<u8>,
+}
+
+impl Packer {
+    pub fn new(version: u8) -> Self {
+        Self {
+            bytes: vec![version],
+        }
+    }
+
+    /// Writes a single raw byte, e.g. the variant index of an enum.
+    pub fn pack_u8(&mut self, value: u8) {
+        self.bytes.push(value);
+    }
+
+    /// Writes [value] as a length-prefixed tagged value.
+    pub fn pack<T: Store>(&mut self, value: &T) {
+        let value_bytes = value.to_tagged_bytes();
+        let len = u32::try_from(value_bytes.len()).expect("value exceeds u32::MAX bytes");
+        self.bytes.extend_from_slice(&len.to_be_bytes());
+        self.bytes.extend_from_slice(&value_bytes);
+    }
+
+    pub fn finish(self) -> Vec<u8> {
+        self.bytes
+    }
+}
+
+/// Unpacker reads the layout written by [Packer].
+pub struct Unpacker<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Unpacker<'a> {
+    /// Returns an Unpacker over [bytes] if they were packed with [version].
+    pub fn new(bytes: &'a [u8], version: u8) -> Result<Self, StorageError> {
+        match bytes.split_first() {
+            Some((found, rest)) if *found == version => Ok(Self { bytes: rest }),
+            Some((found, _)) => Err(StorageError::InvalidVersion(*found)),
+            None => Err(StorageError::InvalidByteLength(0)),
+        }
+    }
+
+    pub fn unpack_u8(&mut self) -> Result<u8, StorageError> {
+        let (value, rest) = self
+            .bytes
+            .split_first()
+            .ok_or(StorageError::InvalidByteLength(0))?;
+        self.bytes = rest;
+        Ok(*value)
+    }
+
+    /// Reads the next length-prefixed value and decodes it as [T].
+    pub fn unpack<T: Store>(&mut self) -> Result<T, StorageError> {
+        if self.bytes.len() < LEN_PREFIX_SIZE {
+            return Err(StorageError::InvalidByteLength(self.bytes.len()));
+        }
+        let (len_bytes, rest) = self.bytes.split_at(LEN_PREFIX_SIZE);
+        let len = u32::from_be_bytes(len_bytes.try_into().expect("4 byte prefix")) as usize;
+        if rest.len() < len {
+            return Err(StorageError::InvalidByteLength(len));
+        }
+        let (value_bytes, rest) = rest.split_at(len);
+        self.bytes = rest;
+        T::from_bytes(value_bytes)
+    }
+
+    /// Ensures every byte has been consumed.
+    pub fn finish(self) -> Result<(), StorageError> {
+        match self.bytes.len() {
+            0 => Ok(()),
+            len => Err(StorageError::InvalidByteLength(len)),
+        }
+    }
+}
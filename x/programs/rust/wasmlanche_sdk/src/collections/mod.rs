@@ -0,0 +1,198 @@
+use crate::errors::StorageError;
+use crate::store::{
+    delete_key, get_field_as_bytes, get_map_field, get_map_key, has_key, remove_key,
+    store_key_value, ProgramContext, Store,
+};
+use std::collections::VecDeque;
+use std::marker::PhantomData;
+
+/// Page is a batch of entries read from a collection in key order.
+pub struct Page<K, V> {
+    pub entries: Vec<(K, V)>,
+    /// The key the following page starts at, or None if this is the last page.
+    pub next: Option<K>,
+}
+
+/// StorageMap is a typed handle to a map stored in the host under [name].
+/// Keys and values are encoded with their [Store] implementations.
+pub struct StorageMap<K, V> {
+    ctx: ProgramContext,
+    name: &'static str,
+    _marker: PhantomData<fn() -> (K, V)>,
+}
+
+// Implemented by hand since deriving would require K and V to be Clone.
+impl<K, V> Clone for StorageMap<K, V> {
+    fn clone(&self) -> Self {
+        Self {
+            ctx: self.ctx.clone(),
+            name: self.name,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<K: Store, V: Store> StorageMap<K, V> {
+    pub fn new(ctx: ProgramContext, name: &'static str) -> Self {
+        Self {
+            ctx,
+            name,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Returns the name of the map in the host.
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Gets the value stored at [key], or None if the key is not set.
+    pub fn get(&self, key: &K) -> Result<Option<V>, StorageError> {
+        get_map_field(&self.ctx, self.name, key)
+    }
+
+    /// Returns whether a value is stored at [key].
+    pub fn contains_key(&self, key: &K) -> Result<bool, StorageError> {
+        has_key(&self.ctx, &get_map_key(self.name, key))
+    }
+
+    /// Stores [value] at [key], replacing any existing value.
+    pub fn insert(&self, key: &K, value: &V) -> Result<(), StorageError> {
+        store_key_value(&self.ctx, &get_map_key(self.name, key), value)
+    }
+
+    /// Removes [key] from the map and returns its previous value, if any.
+    pub fn remove(&self, key: &K) -> Result<Option<V>, StorageError> {
+        remove_key(&self.ctx, &get_map_key(self.name, key))
+    }
+
+    /// Replaces the value at [key] with the result of [f], which receives the current
+    /// value if one is set. Returning None removes the key.
+    pub fn update<F>(&self, key: &K, f: F) -> Result<(), StorageError>
+    where
+        F: FnOnce(Option<V>) -> Option<V>,
+    {
+        let map_key = get_map_key(self.name, key);
+        let current = get_field_as_bytes(&self.ctx, &map_key)?
+            .map(|bytes| V::from_bytes(&bytes))
+            .transpose()?;
+        match f(current) {
+            Some(value) => store_key_value(&self.ctx, &map_key, &value),
+            None => delete_key(&self.ctx, &map_key),
+        }
+    }
+
+    /// Reads up to [limit] entries ordered by their encoded keys, starting at [start]
+    /// (inclusive) and stopping before [end] when they are set.
+    /// Returns an `InvalidLimit` error if [limit] is 0.
+    pub fn page(
+        &self,
+        start: Option<&K>,
+        end: Option<&K>,
+        limit: u32,
+    ) -> Result<Page<K, V>, StorageError> {
+        self.ctx.iter_map(self.name, start, end, limit)
+    }
+
+    /// Returns an iterator over every entry in the map, reading [page_limit]
+    /// entries from the host at a time.
+    pub fn iter(&self, page_limit: u32) -> MapIter<K, V> {
+        MapIter {
+            map: self.clone(),
+            page_limit: page_limit.max(1),
+            entries: VecDeque::new(),
+            next: None,
+            done: false,
+        }
+    }
+}
+
+/// StorageValue is a typed handle to a single value stored in the host under [name].
+pub struct StorageValue<T> {
+    ctx: ProgramContext,
+    name: &'static str,
+    _marker: PhantomData<fn() -> T>,
+}
+
+// Implemented by hand since deriving would require T to be Clone.
+impl<T> Clone for StorageValue<T> {
+    fn clone(&self) -> Self {
+        Self {
+            ctx: self.ctx.clone(),
+            name: self.name,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T: Store> StorageValue<T> {
+    pub fn new(ctx: ProgramContext, name: &'static str) -> Self {
+        Self {
+            ctx,
+            name,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Returns the name of the value in the host.
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Gets the stored value, or None if it is not set.
+    pub fn get(&self) -> Result<Option<T>, StorageError> {
+        self.ctx.get_value(self.name)
+    }
+
+    /// Stores [value], replacing any existing value.
+    pub fn set(&self, value: &T) -> Result<(), StorageError> {
+        self.ctx.store_value(self.name, value)
+    }
+
+    /// Removes the value and returns it, if it was set.
+    pub fn remove(&self) -> Result<Option<T>, StorageError> {
+        self.ctx.remove_value(self.name)
+    }
+
+    /// Replaces the value with the result of [f], which receives the current value if
+    /// one is set. Returning None removes the value.
+    pub fn update<F>(&self, f: F) -> Result<(), StorageError>
+    where
+        F: FnOnce(Option<T>) -> Option<T>,
+    {
+        match f(self.get()?) {
+            Some(value) => self.set(&value),
+            None => self.remove().map(|_| ()),
+        }
+    }
+}
+
+/// MapIter lazily reads the entries of a [StorageMap] one page at a time.
+pub struct MapIter<K, V> {
+    map: StorageMap<K, V>,
+    page_limit: u32,
+    entries: VecDeque<(K, V)>,
+    next: Option<K>,
+    done: bool,
+}
+
+impl<K: Store, V: Store> Iterator for MapIter<K, V> {
+    type Item = Result<(K, V), StorageError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.entries.is_empty() && !self.done {
+            match self.map.page(self.next.as_ref(), None, self.page_limit) {
+                Ok(page) => {
+                    self.entries.extend(page.entries);
+                    self.done = page.next.is_none();
+                    self.next = page.next;
+                }
+                Err(err) => {
+                    self.done = true;
+                    return Some(Err(err));
+                }
+            }
+        }
+        self.entries.pop_front().map(Ok)
+    }
+}
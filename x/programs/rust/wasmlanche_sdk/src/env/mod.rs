@@ -0,0 +1,133 @@
+//! The block and transaction a program runs in, read from the host's env module.
+//! Programs built for anything but WASM, such as their off-chain tests, read them
+//! from a mock set with [mock::set] instead.
+
+/// The id of a chain or transaction.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Hash, Debug)]
+pub struct Id {
+    bytes: [u8; Self::LEN],
+}
+
+impl Id {
+    pub const LEN: usize = 32;
+
+    pub fn new(bytes: [u8; Self::LEN]) -> Self {
+        Self { bytes }
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+/// Env holds every value of the environment at once.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub struct Env {
+    pub height: u64,
+    /// Milliseconds since the unix epoch.
+    pub timestamp: u64,
+    pub chain_id: Id,
+    pub tx_id: Id,
+}
+
+/// Returns the height of the block the program runs in.
+pub fn height() -> u64 {
+    backend::height()
+}
+
+/// Returns the timestamp of the block the program runs in, in milliseconds since the unix epoch.
+pub fn timestamp() -> u64 {
+    backend::timestamp()
+}
+
+/// Returns the id of the chain the program runs on.
+///
+/// # Panics
+/// Panics, failing the call, if the host did not provide it.
+pub fn chain_id() -> Id {
+    backend::chain_id()
+}
+
+/// Returns the id of the transaction the program runs in.
+///
+/// # Panics
+/// Panics, failing the call, if the host did not provide it.
+pub fn tx_id() -> Id {
+    backend::tx_id()
+}
+
+/// Returns the whole environment.
+pub fn get() -> Env {
+    Env {
+        height: height(),
+        timestamp: timestamp(),
+        chain_id: chain_id(),
+        tx_id: tx_id(),
+    }
+}
+
+#[cfg(target_family = "wasm")]
+mod backend {
+    use super::Id;
+    use crate::host;
+
+    pub(super) fn height() -> u64 {
+        host::block_height()
+    }
+
+    pub(super) fn timestamp() -> u64 {
+        host::block_timestamp()
+    }
+
+    pub(super) fn chain_id() -> Id {
+        Id::new(host::chain_id().expect("the host did not provide a chain id"))
+    }
+
+    pub(super) fn tx_id() -> Id {
+        Id::new(host::tx_id().expect("the host did not provide a transaction id"))
+    }
+}
+
+#[cfg(not(target_family = "wasm"))]
+mod backend {
+    use super::{mock, Id};
+
+    pub(super) fn height() -> u64 {
+        mock::get().height
+    }
+
+    pub(super) fn timestamp() -> u64 {
+        mock::get().timestamp
+    }
+
+    pub(super) fn chain_id() -> Id {
+        mock::get().chain_id
+    }
+
+    pub(super) fn tx_id() -> Id {
+        mock::get().tx_id
+    }
+}
+
+/// The environment seen by programs built for anything but WASM, so their logic can be
+/// tested off-chain. Each thread has its own environment, which starts out as
+/// [Env::default].
+#[cfg(not(target_family = "wasm"))]
+pub mod mock {
+    use super::Env;
+    use std::cell::Cell;
+
+    thread_local! {
+        static ENV: Cell<Env> = Cell::new(Env::default());
+    }
+
+    /// Sets the environment returned to the current thread.
+    pub fn set(env: Env) {
+        ENV.with(|cell| cell.set(env));
+    }
+
+    /// Returns the environment of the current thread.
+    pub fn get() -> Env {
+        ENV.with(Cell::get)
+    }
+}
@@ -1,3 +1,7 @@
+use crate::host::{
+    INVOKE_ALREADY_INITIALIZED, INVOKE_INSUFFICIENT_UNITS, INVOKE_INVALID_ARGUMENTS, INVOKE_TRAP,
+    INVOKE_UNKNOWN_METHOD,
+};
 use thiserror::Error;
 
 #[derive(Clone, Error, Debug)]
@@ -14,9 +18,85 @@ pub enum StorageError {
     #[error("Invalid Tag: {0}")]
     InvalidTag(u8),
 
-    #[error("Error Storing Bytes In The Host")]
-    HostStoreError(),
+    #[error("Type Mismatch: expected tag {expected}, found tag {found}")]
+    TypeMismatch { expected: u8, found: u8 },
 
-    #[error("Error Retrieving Bytes In The Host")]
-    HostRetrieveError(),
+    #[error("Invalid Version: {0}")]
+    InvalidVersion(u8),
+
+    #[error("Invalid Variant: {0}")]
+    InvalidVariant(u8),
+
+    #[error("Error Storing Bytes In The Host: {0}")]
+    HostStoreError(i32),
+
+    #[error("Error Retrieving Bytes In The Host: {0}")]
+    HostRetrieveError(i32),
+
+    #[error("Error Deleting Bytes In The Host: {0}")]
+    HostDeleteError(i32),
+
+    #[error("Invalid Limit: {0}")]
+    InvalidLimit(u32),
+}
+
+/// CallError describes why invoking another program failed.
+#[derive(Clone, Error, Debug)]
+pub enum CallError {
+    #[error("Called Program Trapped Or Reverted")]
+    Trap,
+
+    #[error("Called Program Does Not Expose The Method")]
+    UnknownMethod,
+
+    #[error("Invalid Arguments For The Called Method")]
+    InvalidArguments,
+
+    #[error("Insufficient Units To Complete The Call")]
+    InsufficientUnits,
+
+    #[error("Called Program Is Already Initialized")]
+    AlreadyInitialized,
+
+    #[error("Error Invoking The Program In The Host: {0}")]
+    Host(i64),
+
+    #[error("{0}")]
+    Decode(#[from] StorageError),
+}
+
+impl CallError {
+    /// Returns the error described by the [status] the host returned for a call, or None
+    /// if the call succeeded. Any negative status is a failure.
+    pub fn from_status(status: i64) -> Option<Self> {
+        match status {
+            INVOKE_TRAP => Some(CallError::Trap),
+            INVOKE_UNKNOWN_METHOD => Some(CallError::UnknownMethod),
+            INVOKE_INVALID_ARGUMENTS => Some(CallError::InvalidArguments),
+            INVOKE_INSUFFICIENT_UNITS => Some(CallError::InsufficientUnits),
+            INVOKE_ALREADY_INITIALIZED => Some(CallError::AlreadyInitialized),
+            code if code < 0 => Some(CallError::Host(code)),
+            _ => None,
+        }
+    }
+}
+
+/// EventError describes why emitting an event failed.
+#[derive(Clone, Error, Debug)]
+pub enum EventError {
+    #[error("Error Emitting The Event In The Host: {0}")]
+    Host(i32),
+}
+
+/// MathError describes why an arithmetic operation in [crate::math] failed.
+#[derive(Clone, Copy, Error, Debug, PartialEq, Eq)]
+pub enum MathError {
+    #[error("Result Is Greater Than The Maximum Value")]
+    Overflow,
+
+    #[error("Result Is Less Than The Minimum Value")]
+    Underflow,
+
+    #[error("Division By Zero")]
+    DivisionByZero,
 }
@@ -0,0 +1,38 @@
+//! Events tell indexers what a program did, such as a token transfer, without diffing its
+//! storage. A program emits an event with [crate::store::ProgramContext::emit_event], and the
+//! host collects the events of every program a transaction runs. The events of a call that
+//! fails are discarded along with its state changes.
+
+use crate::errors::StorageError;
+
+/// A kind of event a program emits, usually implemented with `#[derive(Event)]` from the
+/// `storable_macro` crate.
+pub trait Event: Sized {
+    /// Identifies the kind of event to indexers, e.g. `Transfer`.
+    const TOPIC: &'static str;
+
+    /// Encodes the fields of the event.
+    fn payload(&self) -> Vec<u8>;
+
+    /// Decodes an event from the [payload] it was emitted with.
+    fn from_payload(payload: &[u8]) -> Result<Self, StorageError>;
+}
+
+/// An event emitted by a program, as collected by the host.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EmittedEvent {
+    pub program_id: u64,
+    pub topic: String,
+    pub payload: Vec<u8>,
+}
+
+impl EmittedEvent {
+    /// Decodes the payload as [E], or returns None if the event has another topic or its
+    /// payload is not an [E].
+    pub fn decode<E: Event>(&self) -> Option<E> {
+        if self.topic != E::TOPIC {
+            return None;
+        }
+        E::from_payload(&self.payload).ok()
+    }
+}
@@ -1,40 +1,32 @@
-use crate::store::ProgramContext;
-
-// The map module contains functionality for storing and retrieving key-value pairs.
-#[link(wasm_import_module = "map")]
-extern "C" {
-    #[link_name = "init_program"]
-    fn _init_program() -> i64;
-
-    #[link_name = "store_bytes"]
-    fn _store_bytes(
-        contractId: u64,
-        key_ptr: *const u8,
-        key_len: usize,
-        value_ptr: *const u8,
-        value_len: usize,
-    ) -> i32;
-
-    #[link_name = "get_bytes_len"]
-    fn _get_bytes_len(contract_id: u64, key_ptr: *const u8, key_len: usize) -> i32;
-
-    #[link_name = "get_bytes"]
-    fn _get_bytes(contract_id: u64, key_ptr: *const u8, key_len: usize, val_len: i32) -> i32;
-}
-
-// The program module contains functionality for invoking external programs.
-#[link(wasm_import_module = "program")]
-extern "C" {
-    #[link_name = "invoke_program"]
-    fn _invoke_program(
-        contract_id: u64,
-        call_contract_id: u64,
-        method_name_ptr: *const u8,
-        method_name_len: usize,
-        args_ptr: *const u8,
-        args_len: usize,
-    ) -> i64;
-}
+use crate::store::{ProgramContext, Store};
+use crate::types::Address;
+
+/// Returned by the map module when no value is stored at the requested key.
+pub const MAP_NOT_FOUND: i32 = -2;
+/// Returned by the map module when no storage exists for the program.
+pub const MAP_UNKNOWN_PROGRAM: i32 = -3;
+
+/// Returned by program_invoke when the callee traps, e.g. by panicking, or reverts.
+pub const INVOKE_TRAP: i64 = -2;
+/// Returned by program_invoke when the callee does not expose the method.
+pub const INVOKE_UNKNOWN_METHOD: i64 = -3;
+/// Returned by program_invoke when the arguments could not be passed to the callee.
+pub const INVOKE_INVALID_ARGUMENTS: i64 = -4;
+/// Returned by program_invoke when the call ran out of units.
+pub const INVOKE_INSUFFICIENT_UNITS: i64 = -5;
+/// Returned by program_invoke when the `#[init]` method of the callee already ran.
+pub const INVOKE_ALREADY_INITIALIZED: i64 = -6;
+
+// The host imports are generated by build.rs from the crate's copy of the ABI manifest
+// in x/programs/abi, which the runtime checks its host modules against. The map module
+// contains functionality for storing and retrieving key-value pairs, the program module
+// contains functionality for invoking external programs, the context module tells a
+// program who it runs for, the env module the block and transaction it runs in, and
+// the meter module the units it has left, and the event module collects the events it
+// emits.
+include!(concat!(env!("OUT_DIR"), "/abi.rs"));
+#[cfg(mock_host)]
+use crate::mock::imports::*;
 
 /* wrappers for unsafe imported functions ----- */
 /// Returns the map_id or None if there was an error
@@ -58,6 +50,7 @@ pub unsafe fn store_bytes(
 }
 
 /// Gets the length of the bytes associated with the key from the host.
+/// Returns [MAP_NOT_FOUND] if the key is not set, or another negative error code on failure.
 ///
 /// # Safety
 /// The caller must ensure that key_ptr + key_len points to valid memory locations.
@@ -78,7 +71,58 @@ pub unsafe fn get_bytes(
     unsafe { _get_bytes(ctx.program_id, key_ptr, key_len, val_len) }
 }
 
-/// Invokes another program and returns the result.
+/// Deletes the bytes associated with the key from the host.
+/// Returns [MAP_NOT_FOUND] if the key was not set.
+///
+/// # Safety
+/// The caller must ensure that key_ptr + key_len points to valid memory locations.
+pub unsafe fn delete_bytes(ctx: &ProgramContext, key_ptr: *const u8, key_len: usize) -> i32 {
+    unsafe { _delete_bytes(ctx.program_id, key_ptr, key_len) }
+}
+
+/// Reads up to [limit] entries whose keys start with [prefix], in ascending key order.
+/// Only keys >= [start] and < [end] are returned, and an empty bound is unbounded.
+///
+/// The entries are written to a buffer allocated in the module, each as a big endian
+/// u32 key length, the key, a big endian u32 value length and the value. Returns the
+/// offset of the buffer in the upper 32 bits and its length in the lower 32 bits,
+/// or a negative error code on failure.
+pub fn iter_prefix(
+    ctx: &ProgramContext,
+    prefix: &[u8],
+    start: &[u8],
+    end: &[u8],
+    limit: u32,
+) -> i64 {
+    unsafe {
+        _iter_prefix(
+            ctx.program_id,
+            prefix.as_ptr(),
+            prefix.len(),
+            start.as_ptr(),
+            start.len(),
+            end.as_ptr(),
+            end.len(),
+            limit,
+        )
+    }
+}
+
+/// Returns a savepoint of the values stored by every program and the events emitted, which
+/// [revert] restores.
+pub fn savepoint() -> i64 {
+    unsafe { _savepoint() }
+}
+
+/// Discards every value stored or deleted and every event emitted since [savepoint] was
+/// taken, along with any savepoints taken after it. Returns a negative error code if the savepoint is unknown.
+pub fn revert(savepoint: i64) -> i32 {
+    unsafe { _revert(savepoint) }
+}
+
+/// Invokes another program. Returns the offset of a buffer holding the tagged result of
+/// the call in the upper 32 bits and its length in the lower 32 bits, or a negative
+/// status such as [INVOKE_TRAP] on failure.
 pub fn host_program_invoke(
     ctx: &ProgramContext,
     call_ctx: &ProgramContext,
@@ -98,6 +142,130 @@ pub fn host_program_invoke(
     }
 }
 
+/// Invokes another program like [host_program_invoke], allowing the callee to consume at
+/// most [max_units]. A callee running out returns [INVOKE_INSUFFICIENT_UNITS] without
+/// consuming more than [max_units] of the caller's units.
+pub fn host_program_invoke_with_units(
+    ctx: &ProgramContext,
+    call_ctx: &ProgramContext,
+    method_name: &str,
+    args: &[u8],
+    max_units: u64,
+) -> i64 {
+    let method_name_bytes = method_name.as_bytes();
+    unsafe {
+        _invoke_program_with_units(
+            ctx.program_id,
+            call_ctx.program_id,
+            method_name_bytes.as_ptr(),
+            method_name_bytes.len(),
+            args.as_ptr(),
+            args.len(),
+            max_units,
+        )
+    }
+}
+
+/// Emits an event with [topic] and [payload] from the program [ctx]. The host records it
+/// for the program it is running, whichever program [ctx] is. Returns a negative error code
+/// if the host rejected it.
+pub fn emit_event(ctx: &ProgramContext, topic: &str, payload: &[u8]) -> i32 {
+    unsafe {
+        _emit_event(
+            ctx.program_id,
+            topic.as_ptr(),
+            topic.len(),
+            payload.as_ptr(),
+            payload.len(),
+        )
+    }
+}
+
+/// Returns the address of the transaction signer, or None if the host did not provide one.
+pub fn actor() -> Option<Address> {
+    let mut bytes = [0; Address::LEN];
+    match unsafe { _actor(bytes.as_mut_ptr()) } {
+        0 => Some(Address::new(bytes)),
+        _ => None,
+    }
+}
+
+/// Returns the program that invoked the running program, or None if the actor called it directly.
+pub fn caller() -> Option<ProgramContext> {
+    match unsafe { _caller() } {
+        0 => None,
+        program_id => Some(ProgramContext::from(program_id)),
+    }
+}
+
+/// Returns the units the program has left to consume.
+pub fn remaining_units() -> u64 {
+    unsafe { _remaining_units() as u64 }
+}
+
+/// Returns the height of the block the program runs in.
+pub fn block_height() -> u64 {
+    unsafe { _height() as u64 }
+}
+
+/// Returns the timestamp of the block the program runs in, in milliseconds since the unix epoch.
+pub fn block_timestamp() -> u64 {
+    unsafe { _timestamp() as u64 }
+}
+
+/// Returns the id of the chain the program runs on, or None if the host did not provide one.
+pub fn chain_id() -> Option<[u8; 32]> {
+    let mut bytes = [0; 32];
+    match unsafe { _chain_id(bytes.as_mut_ptr()) } {
+        0 => Some(bytes),
+        _ => None,
+    }
+}
+
+/// Returns the id of the transaction the program runs in, or None if the host did not provide one.
+pub fn tx_id() -> Option<[u8; 32]> {
+    let mut bytes = [0; 32];
+    match unsafe { _tx_id(bytes.as_mut_ptr()) } {
+        0 => Some(bytes),
+        _ => None,
+    }
+}
+
+/// Hands the tagged bytes of [result] to the host as the return value of the current call,
+/// so that a calling program can decode it. Exposed functions do this automatically.
+pub fn set_call_result<T: Store>(result: &T) -> i32 {
+    let bytes = result.to_tagged_bytes();
+    unsafe { _set_call_result(bytes.as_ptr(), bytes.len()) }
+}
+
+/// Fails the current call with [code], e.g. [INVOKE_INVALID_ARGUMENTS], which the host
+/// returns to the calling program instead of the call's result. Exposed functions do this
+/// automatically when their arguments can not be decoded.
+pub fn set_call_error(code: i64) -> i32 {
+    unsafe { _set_call_error(code) }
+}
+
+/// Takes ownership of the buffer of [len] bytes the host wrote to [ptr] in this module's memory.
+///
+/// # Safety
+/// [ptr] must be a buffer of [len] bytes the host allocated with [alloc] and handed over.
+pub(crate) unsafe fn take_host_buffer(ptr: usize, len: usize) -> Vec<u8> {
+    if len == 0 {
+        return Vec::new();
+    }
+    #[cfg(mock_host)]
+    return crate::mock::take_buffer(ptr, len);
+    #[cfg(not(mock_host))]
+    Vec::from_raw_parts(ptr as *mut u8, len, len)
+}
+
+/// Returns the version of the host ABI the program was built against, which the
+/// runtime checks before running the program.
+#[no_mangle]
+pub fn abi_version() -> u32 {
+    ABI_VERSION
+}
+
 /* memory functions ------------------------------------------- */
 // https://radu-matei.com/blog/practical-guide-to-wasm-memory/
 
@@ -1,5 +1,13 @@
+pub mod codec;
+pub mod collections;
+pub mod env;
 pub mod errors;
+pub mod event;
 pub mod host;
+pub mod math;
+pub mod meter;
+#[cfg(mock_host)]
+pub mod mock;
 pub mod program;
 pub mod store;
 pub mod types;
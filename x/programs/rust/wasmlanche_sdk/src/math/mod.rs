@@ -0,0 +1,333 @@
+use crate::codec::{Packer, Unpacker};
+use crate::errors::{MathError, StorageError};
+use crate::store::{Store, Tag};
+use crate::types::U256;
+use std::borrow::Cow;
+
+/// Integer is implemented by every integer type the SDK stores, so the functions of
+/// this module can be used with any of them.
+pub trait Integer: Copy + Ord {
+    const ZERO: Self;
+    const MIN: Self;
+    const MAX: Self;
+
+    fn checked_add(self, rhs: Self) -> Option<Self>;
+    fn checked_sub(self, rhs: Self) -> Option<Self>;
+    fn checked_mul(self, rhs: Self) -> Option<Self>;
+    /// Returns self * mul / div rounded towards zero, computing the product without
+    /// overflowing. Returns None if [div] is zero or the result does not fit.
+    fn checked_mul_div(self, mul: Self, div: Self) -> Option<Self>;
+}
+
+/// Returns a + b.
+pub fn checked_add<T: Integer>(a: T, b: T) -> Result<T, MathError> {
+    a.checked_add(b).ok_or(if b < T::ZERO {
+        MathError::Underflow
+    } else {
+        MathError::Overflow
+    })
+}
+
+/// Returns a - b.
+pub fn checked_sub<T: Integer>(a: T, b: T) -> Result<T, MathError> {
+    a.checked_sub(b).ok_or(if b < T::ZERO {
+        MathError::Overflow
+    } else {
+        MathError::Underflow
+    })
+}
+
+/// Returns a * b.
+pub fn checked_mul<T: Integer>(a: T, b: T) -> Result<T, MathError> {
+    a.checked_mul(b).ok_or(if (a < T::ZERO) != (b < T::ZERO) {
+        MathError::Underflow
+    } else {
+        MathError::Overflow
+    })
+}
+
+/// Returns a * b / c rounded towards zero. The product does not need to fit in T, only
+/// the result does, so e.g. a share of a balance can be computed without overflowing.
+pub fn checked_mul_div<T: Integer>(a: T, b: T, c: T) -> Result<T, MathError> {
+    if c == T::ZERO {
+        return Err(MathError::DivisionByZero);
+    }
+    let negative = (a < T::ZERO) ^ (b < T::ZERO) ^ (c < T::ZERO);
+    a.checked_mul_div(b, c).ok_or(if negative {
+        MathError::Underflow
+    } else {
+        MathError::Overflow
+    })
+}
+
+/// Returns a + b, bounded by the minimum and maximum value of T.
+pub fn saturating_add<T: Integer>(a: T, b: T) -> T {
+    saturate(checked_add(a, b))
+}
+
+/// Returns a - b, bounded by the minimum and maximum value of T.
+pub fn saturating_sub<T: Integer>(a: T, b: T) -> T {
+    saturate(checked_sub(a, b))
+}
+
+/// Returns a * b, bounded by the minimum and maximum value of T.
+pub fn saturating_mul<T: Integer>(a: T, b: T) -> T {
+    saturate(checked_mul(a, b))
+}
+
+/// Returns a * b / c rounded towards zero, bounded by the minimum and maximum value of T.
+/// Only fails if c is zero.
+pub fn saturating_mul_div<T: Integer>(a: T, b: T, c: T) -> Result<T, MathError> {
+    match checked_mul_div(a, b, c) {
+        Err(MathError::DivisionByZero) => Err(MathError::DivisionByZero),
+        result => Ok(saturate(result)),
+    }
+}
+
+fn saturate<T: Integer>(result: Result<T, MathError>) -> T {
+    match result {
+        Ok(value) => value,
+        Err(MathError::Underflow) => T::MIN,
+        Err(_) => T::MAX,
+    }
+}
+
+/// Implements Integer for a primitive using std's checked operations, computing
+/// mul_div in a type wide enough to hold any product.
+macro_rules! impl_integer {
+    ($($int:ty => $wide:ty),* $(,)?) => {
+        $(
+            impl Integer for $int {
+                const ZERO: Self = 0;
+                const MIN: Self = <$int>::MIN;
+                const MAX: Self = <$int>::MAX;
+
+                fn checked_add(self, rhs: Self) -> Option<Self> {
+                    <$int>::checked_add(self, rhs)
+                }
+
+                fn checked_sub(self, rhs: Self) -> Option<Self> {
+                    <$int>::checked_sub(self, rhs)
+                }
+
+                fn checked_mul(self, rhs: Self) -> Option<Self> {
+                    <$int>::checked_mul(self, rhs)
+                }
+
+                fn checked_mul_div(self, mul: Self, div: Self) -> Option<Self> {
+                    let result = (<$wide>::from(self) * <$wide>::from(mul))
+                        .checked_div(<$wide>::from(div))?;
+                    <$int>::try_from(result).ok()
+                }
+            }
+        )*
+    };
+}
+
+impl_integer!(i32 => i64, i64 => i128, u64 => u128);
+
+impl Integer for u128 {
+    const ZERO: Self = 0;
+    const MIN: Self = u128::MIN;
+    const MAX: Self = u128::MAX;
+
+    fn checked_add(self, rhs: Self) -> Option<Self> {
+        u128::checked_add(self, rhs)
+    }
+
+    fn checked_sub(self, rhs: Self) -> Option<Self> {
+        u128::checked_sub(self, rhs)
+    }
+
+    fn checked_mul(self, rhs: Self) -> Option<Self> {
+        u128::checked_mul(self, rhs)
+    }
+
+    fn checked_mul_div(self, mul: Self, div: Self) -> Option<Self> {
+        let result = U256::from(self).checked_mul_div(U256::from(mul), U256::from(div))?;
+        u128::try_from(result).ok()
+    }
+}
+
+impl Integer for i128 {
+    const ZERO: Self = 0;
+    const MIN: Self = i128::MIN;
+    const MAX: Self = i128::MAX;
+
+    fn checked_add(self, rhs: Self) -> Option<Self> {
+        i128::checked_add(self, rhs)
+    }
+
+    fn checked_sub(self, rhs: Self) -> Option<Self> {
+        i128::checked_sub(self, rhs)
+    }
+
+    fn checked_mul(self, rhs: Self) -> Option<Self> {
+        i128::checked_mul(self, rhs)
+    }
+
+    fn checked_mul_div(self, mul: Self, div: Self) -> Option<Self> {
+        // divide the magnitudes, then restore the sign
+        let magnitude = self
+            .unsigned_abs()
+            .checked_mul_div(mul.unsigned_abs(), div.unsigned_abs())?;
+        if (self < 0) ^ (mul < 0) ^ (div < 0) {
+            0_i128.checked_sub_unsigned(magnitude)
+        } else {
+            i128::try_from(magnitude).ok()
+        }
+    }
+}
+
+impl Integer for U256 {
+    const ZERO: Self = U256::ZERO;
+    const MIN: Self = U256::ZERO;
+    const MAX: Self = U256::MAX;
+
+    fn checked_add(self, rhs: Self) -> Option<Self> {
+        let mut sum = limbs(self);
+        match add_assign(&mut sum, &limbs(rhs)) {
+            false => Some(from_limbs(sum)),
+            true => None,
+        }
+    }
+
+    fn checked_sub(self, rhs: Self) -> Option<Self> {
+        let mut difference = limbs(self);
+        match sub_assign(&mut difference, &limbs(rhs)) {
+            false => Some(from_limbs(difference)),
+            true => None,
+        }
+    }
+
+    fn checked_mul(self, rhs: Self) -> Option<Self> {
+        let product = wide_mul(&limbs(self), &limbs(rhs));
+        match product[4..].iter().all(|limb| *limb == 0) {
+            true => Some(from_limbs(product[..4].try_into().expect("4 limbs"))),
+            false => None,
+        }
+    }
+
+    fn checked_mul_div(self, mul: Self, div: Self) -> Option<Self> {
+        if div == U256::ZERO {
+            return None;
+        }
+        let product = wide_mul(&limbs(self), &limbs(mul));
+        let divisor = limbs(div);
+        // long division one bit at a time, the remainder needs a limb more than the
+        // divisor as it is shifted before being reduced
+        let mut remainder = [0_u64; 5];
+        let mut quotient = [0_u64; 8];
+        let divisor = [divisor[0], divisor[1], divisor[2], divisor[3], 0];
+        for bit in (0..64 * product.len()).rev() {
+            shl_one(&mut remainder, (product[bit / 64] >> (bit % 64)) & 1);
+            if !less_than(&remainder, &divisor) {
+                sub_assign(&mut remainder, &divisor);
+                quotient[bit / 64] |= 1 << (bit % 64);
+            }
+        }
+        match quotient[4..].iter().all(|limb| *limb == 0) {
+            true => Some(from_limbs(quotient[..4].try_into().expect("4 limbs"))),
+            false => None,
+        }
+    }
+}
+
+/// Returns the 64 bit limbs of [value], least significant first.
+fn limbs(value: U256) -> [u64; 4] {
+    let bytes = value.to_be_bytes();
+    let mut limbs = [0; 4];
+    for (index, limb) in limbs.iter_mut().enumerate() {
+        let end = U256::LEN - 8 * index;
+        *limb = u64::from_be_bytes(bytes[end - 8..end].try_into().expect("8 bytes"));
+    }
+    limbs
+}
+
+fn from_limbs(limbs: [u64; 4]) -> U256 {
+    let mut bytes = [0; U256::LEN];
+    for (index, limb) in limbs.iter().enumerate() {
+        let end = U256::LEN - 8 * index;
+        bytes[end - 8..end].copy_from_slice(&limb.to_be_bytes());
+    }
+    U256::from_be_bytes(bytes)
+}
+
+/// Adds [rhs] to [lhs] and returns whether the sum overflowed.
+fn add_assign(lhs: &mut [u64], rhs: &[u64]) -> bool {
+    let mut carry = false;
+    for (lhs, rhs) in lhs.iter_mut().zip(rhs) {
+        let (sum, overflow) = lhs.overflowing_add(*rhs);
+        let (sum, carried) = sum.overflowing_add(u64::from(carry));
+        *lhs = sum;
+        carry = overflow || carried;
+    }
+    carry
+}
+
+/// Subtracts [rhs] from [lhs] and returns whether the difference underflowed.
+fn sub_assign(lhs: &mut [u64], rhs: &[u64]) -> bool {
+    let mut borrow = false;
+    for (lhs, rhs) in lhs.iter_mut().zip(rhs) {
+        let (difference, underflow) = lhs.overflowing_sub(*rhs);
+        let (difference, borrowed) = difference.overflowing_sub(u64::from(borrow));
+        *lhs = difference;
+        borrow = underflow || borrowed;
+    }
+    borrow
+}
+
+/// Returns the full 512 bit product of [lhs] and [rhs].
+fn wide_mul(lhs: &[u64; 4], rhs: &[u64; 4]) -> [u64; 8] {
+    let mut product = [0; 8];
+    for (i, lhs) in lhs.iter().enumerate() {
+        let mut carry = 0_u128;
+        for (j, rhs) in rhs.iter().enumerate() {
+            let limb = u128::from(*lhs) * u128::from(*rhs) + u128::from(product[i + j]) + carry;
+            product[i + j] = limb as u64;
+            carry = limb >> 64;
+        }
+        product[i + 4] = carry as u64;
+    }
+    product
+}
+
+/// Shifts [limbs] left by one bit, shifting in [bit].
+fn shl_one(limbs: &mut [u64], bit: u64) {
+    let mut carry = bit;
+    for limb in limbs.iter_mut() {
+        let next = *limb >> 63;
+        *limb = (*limb << 1) | carry;
+        carry = next;
+    }
+}
+
+fn less_than(lhs: &[u64], rhs: &[u64]) -> bool {
+    lhs.iter().rev().lt(rhs.iter().rev())
+}
+
+/// MathError is stored as the layout of an enum deriving `Storable`, so exposed
+/// functions can return `Result<T, MathError>`.
+impl Store for MathError {
+    fn as_bytes(&self) -> Cow<'_, [u8]> {
+        let mut packer = Packer::new(0);
+        packer.pack_u8(*self as u8);
+        Cow::Owned(packer.finish())
+    }
+
+    fn as_tag(&self) -> Tag {
+        Tag::ENUM
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self, StorageError> {
+        let mut unpacker = Unpacker::new(Tag::ENUM.strip(bytes)?, 0)?;
+        let err = match unpacker.unpack_u8()? {
+            0 => MathError::Overflow,
+            1 => MathError::Underflow,
+            2 => MathError::DivisionByZero,
+            variant => return Err(StorageError::InvalidVariant(variant)),
+        };
+        unpacker.finish()?;
+        Ok(err)
+    }
+}
@@ -0,0 +1,10 @@
+//! The units the host charges a program for the instructions it runs. A program that
+//! runs out fails, so callers can bound what a program they call may consume with
+//! [crate::store::ProgramContext::program_invoke_with_units].
+
+use crate::host;
+
+/// Returns the units the program has left to consume.
+pub fn remaining_units() -> u64 {
+    host::remaining_units()
+}
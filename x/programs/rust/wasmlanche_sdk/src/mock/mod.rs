@@ -0,0 +1,534 @@
+//! An in-memory host for testing programs natively, used in place of the runtime's host
+//! modules when the `mock-host` feature is enabled on a target other than WASM.
+//!
+//! Each thread has its own host, so every test starts with no programs and no storage.
+//! Programs are called by calling their functions directly, or through the host with
+//! [call] and [ProgramContext::program_invoke] once their code is registered with [deploy]
+//! or [register]. Storage created while a program runs, e.g. by an `#[init]` method, runs
+//! the same code. Calls through the host that fail discard their changes to storage and
+//! their events. A program that panics fails the test instead of trapping.
+
+use crate::errors::CallError;
+use crate::event::EmittedEvent;
+use crate::host::{
+    INVOKE_INSUFFICIENT_UNITS, INVOKE_UNKNOWN_METHOD, MAP_NOT_FOUND, MAP_UNKNOWN_PROGRAM,
+};
+use crate::program::{method_id, CallArgs, ProgramValue};
+use crate::store::{ProgramContext, Store};
+use crate::types::Address;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
+use std::rc::Rc;
+
+/// Returned by the mock host for failures the runtime reports with its generic error code.
+const HOST_ERR: i32 = -1;
+
+/// Calls the method named by the first argument with the argument buffer.
+pub type Methods = Rc<dyn Fn(&str, *mut u8, usize) -> bool>;
+
+/// How the mock host calls the methods of a program.
+#[derive(Clone)]
+pub enum Entry {
+    /// The `dispatch` function generated by `#[program]`.
+    Dispatch(unsafe extern "C" fn(u32, *mut u8, usize) -> i64),
+    /// Calls a method with the argument buffer, e.g. by passing it to the `#[expose]`
+    /// wrapper of the same name. Returns false if the program does not expose the method,
+    /// in which case the host frees the buffer.
+    Methods(Methods),
+}
+
+impl Entry {
+    pub fn methods<F>(f: F) -> Self
+    where
+        F: Fn(&str, *mut u8, usize) -> bool + 'static,
+    {
+        Entry::Methods(Rc::new(f))
+    }
+}
+
+/// A call executing in the mock host.
+struct Frame {
+    program_id: u64,
+    caller: u64,
+    result: Option<Vec<u8>>,
+    error: i64,
+}
+
+/// The value a key held before it was stored or deleted, restored by reverting.
+struct Change {
+    program_id: u64,
+    key: Vec<u8>,
+    value: Option<Vec<u8>>,
+}
+
+#[derive(Default)]
+struct Host {
+    storage: HashMap<u64, BTreeMap<Vec<u8>, Vec<u8>>>,
+    // every change to storage, so the position in it is a savepoint
+    journal: Vec<Change>,
+    events: Vec<EmittedEvent>,
+    code: HashMap<u64, Entry>,
+    last_program_id: u64,
+    actor: Option<Address>,
+    // units left to the running program, unlimited if not set
+    units: Option<u64>,
+    frames: Vec<Frame>,
+    // buffers handed to the program, by the offset returned in their place
+    buffers: HashMap<usize, Vec<u8>>,
+    last_buffer: usize,
+}
+
+thread_local! {
+    static HOST: RefCell<Host> = RefCell::new(Host::default());
+}
+
+fn with_host<R>(f: impl FnOnce(&mut Host) -> R) -> R {
+    HOST.with(|host| f(&mut host.borrow_mut()))
+}
+
+impl Host {
+    /// Returns a savepoint of storage and the events emitted, packing the number of events
+    /// in the upper 32 bits and the length of the journal in the lower 32 bits.
+    fn savepoint(&self) -> i64 {
+        (self.events.len() as i64) << 32 | self.journal.len() as i64
+    }
+
+    /// Undoes every change to storage made and drops every event emitted after [savepoint].
+    /// Returns false if [savepoint] is not one of the savepoints still held.
+    fn revert(&mut self, savepoint: i64) -> bool {
+        let Ok(savepoint) = u64::try_from(savepoint) else {
+            return false;
+        };
+        let (emitted, changed) = (
+            (savepoint >> 32) as usize,
+            (savepoint & 0xffff_ffff) as usize,
+        );
+        if emitted > self.events.len() || changed > self.journal.len() {
+            return false;
+        }
+        for change in self.journal.drain(changed..).rev() {
+            let storage = self.storage.entry(change.program_id).or_default();
+            match change.value {
+                Some(value) => storage.insert(change.key, value),
+                None => storage.remove(&change.key),
+            };
+        }
+        self.events.truncate(emitted);
+        true
+    }
+}
+
+/// Removes every program, its storage, the events emitted, the actor and the remaining units
+/// from the current thread's host.
+pub fn reset() {
+    with_host(|host| *host = Host::default());
+}
+
+/// Creates a program running [entry], with empty storage.
+pub fn deploy(entry: Entry) -> ProgramContext {
+    let ctx = crate::host::init_program_storage();
+    register(&ctx, entry);
+    ctx
+}
+
+/// Runs [entry] for the existing [program], e.g. one created by calling its `init_program`.
+pub fn register(program: &ProgramContext, entry: Entry) {
+    with_host(|host| host.code.insert(program.program_id, entry));
+}
+
+/// Returns every event emitted since the host was reset, in the order they were emitted.
+pub fn events() -> Vec<EmittedEvent> {
+    with_host(|host| host.events.clone())
+}
+
+/// Sets the address of the transaction signer seen by every program.
+pub fn set_actor(actor: Address) {
+    with_host(|host| host.actor = Some(actor));
+}
+
+/// Sets the units programs see as remaining. Instructions are not metered natively, so
+/// a call through the host only runs out of units if it is given none, e.g. by calling
+/// another program with a cap of 0.
+pub fn set_remaining_units(units: u64) {
+    with_host(|host| host.units = Some(units));
+}
+
+/// Calls [method] on [program] as the actor, and decodes the tagged value it returned as [R].
+pub fn call<R: Store>(
+    program: &ProgramContext,
+    method: &str,
+    args: &[ProgramValue],
+) -> Result<R, CallError> {
+    // 0 is never the id of a program, so the callee sees no caller
+    ProgramContext::from(0).program_invoke(program, method, args)
+}
+
+/// Hands [bytes] to the program in place of a buffer written to its memory, returning
+/// the offset it reads them back from with [take_buffer].
+fn give_buffer(bytes: Vec<u8>) -> usize {
+    with_host(|host| {
+        host.last_buffer += 1;
+        host.buffers.insert(host.last_buffer, bytes);
+        host.last_buffer
+    })
+}
+
+/// Returns the buffer handed to the program at [offset].
+pub(crate) fn take_buffer(offset: usize, len: usize) -> Vec<u8> {
+    let bytes = with_host(|host| host.buffers.remove(&offset))
+        .expect("the mock host did not hand out a buffer at this offset");
+    assert_eq!(bytes.len(), len, "buffer read with the wrong length");
+    bytes
+}
+
+/// # Safety
+/// [ptr] must point to [len] readable bytes.
+unsafe fn read<'a>(ptr: *const u8, len: usize) -> &'a [u8] {
+    match len {
+        0 => &[],
+        _ => std::slice::from_raw_parts(ptr, len),
+    }
+}
+
+/// # Safety
+/// [ptr] must point to [bytes].len() writable bytes.
+unsafe fn write(ptr: *const u8, bytes: &[u8]) {
+    std::ptr::copy_nonoverlapping(bytes.as_ptr(), ptr as *mut u8, bytes.len());
+}
+
+/// The host functions, with the signatures of the imports generated from the ABI manifest.
+pub(crate) mod imports {
+    use super::*;
+
+    pub(crate) unsafe fn _init_program() -> i64 {
+        with_host(|host| {
+            host.last_program_id += 1;
+            let id = host.last_program_id;
+            host.storage.insert(id, BTreeMap::new());
+            // storage created by a running program runs the same code
+            let code = host
+                .frames
+                .last()
+                .and_then(|frame| host.code.get(&frame.program_id).cloned());
+            if let Some(code) = code {
+                host.code.insert(id, code);
+            }
+            id as i64
+        })
+    }
+
+    pub(crate) unsafe fn _store_bytes(
+        contract_id: u64,
+        key_ptr: *const u8,
+        key_len: usize,
+        value_ptr: *const u8,
+        value_len: usize,
+    ) -> i32 {
+        let key = read(key_ptr, key_len).to_vec();
+        let value = read(value_ptr, value_len).to_vec();
+        with_host(|host| match host.storage.get_mut(&contract_id) {
+            Some(storage) => {
+                let value = storage.insert(key.clone(), value);
+                host.journal.push(Change {
+                    program_id: contract_id,
+                    key,
+                    value,
+                });
+                0
+            }
+            None => MAP_UNKNOWN_PROGRAM,
+        })
+    }
+
+    pub(crate) unsafe fn _get_bytes_len(
+        contract_id: u64,
+        key_ptr: *const u8,
+        key_len: usize,
+    ) -> i32 {
+        let key = read(key_ptr, key_len);
+        with_host(|host| match host.storage.get(&contract_id) {
+            Some(storage) => storage
+                .get(key)
+                .map_or(MAP_NOT_FOUND, |value| value.len() as i32),
+            None => MAP_UNKNOWN_PROGRAM,
+        })
+    }
+
+    pub(crate) unsafe fn _get_bytes(
+        contract_id: u64,
+        key_ptr: *const u8,
+        key_len: usize,
+        val_len: i32,
+    ) -> i32 {
+        if val_len < 0 {
+            return HOST_ERR;
+        }
+        let key = read(key_ptr, key_len);
+        let value = with_host(|host| match host.storage.get(&contract_id) {
+            Some(storage) => storage.get(key).cloned().ok_or(MAP_NOT_FOUND),
+            None => Err(MAP_UNKNOWN_PROGRAM),
+        });
+        match value {
+            Ok(value) => give_buffer(value) as i32,
+            Err(code) => code,
+        }
+    }
+
+    pub(crate) unsafe fn _delete_bytes(
+        contract_id: u64,
+        key_ptr: *const u8,
+        key_len: usize,
+    ) -> i32 {
+        let key = read(key_ptr, key_len);
+        with_host(|host| match host.storage.get_mut(&contract_id) {
+            Some(storage) => match storage.remove(key) {
+                Some(value) => {
+                    host.journal.push(Change {
+                        program_id: contract_id,
+                        key: key.to_vec(),
+                        value: Some(value),
+                    });
+                    0
+                }
+                None => MAP_NOT_FOUND,
+            },
+            None => MAP_UNKNOWN_PROGRAM,
+        })
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) unsafe fn _iter_prefix(
+        contract_id: u64,
+        prefix_ptr: *const u8,
+        prefix_len: usize,
+        start_ptr: *const u8,
+        start_len: usize,
+        end_ptr: *const u8,
+        end_len: usize,
+        limit: u32,
+    ) -> i64 {
+        let prefix = read(prefix_ptr, prefix_len);
+        let start = read(start_ptr, start_len);
+        let end = read(end_ptr, end_len);
+        let entries = with_host(|host| {
+            let storage = host.storage.get(&contract_id)?;
+            let mut entries = Vec::new();
+            for (key, value) in storage
+                .range(prefix.to_vec()..)
+                .take_while(|(key, _)| key.starts_with(prefix))
+                .filter(|(key, _)| start.is_empty() || key.as_slice() >= start)
+                .filter(|(key, _)| end.is_empty() || key.as_slice() < end)
+                .take(limit as usize)
+            {
+                entries.extend_from_slice(&(key.len() as u32).to_be_bytes());
+                entries.extend_from_slice(key);
+                entries.extend_from_slice(&(value.len() as u32).to_be_bytes());
+                entries.extend_from_slice(value);
+            }
+            Some(entries)
+        });
+        match entries {
+            None => i64::from(MAP_UNKNOWN_PROGRAM),
+            Some(entries) if entries.is_empty() => 0,
+            Some(entries) => {
+                let len = entries.len() as i64;
+                (give_buffer(entries) as i64) << 32 | len
+            }
+        }
+    }
+
+    pub(crate) unsafe fn _emit_event(
+        contract_id: u64,
+        topic_ptr: *const u8,
+        topic_len: usize,
+        payload_ptr: *const u8,
+        payload_len: usize,
+    ) -> i32 {
+        let Ok(topic) = std::str::from_utf8(read(topic_ptr, topic_len)) else {
+            return HOST_ERR;
+        };
+        let payload = read(payload_ptr, payload_len).to_vec();
+        with_host(|host| {
+            // events belong to the running program, whatever id it passes, while a test
+            // calling a program's functions directly emits them for the program it passes
+            let program_id = host
+                .frames
+                .last()
+                .map_or(contract_id, |frame| frame.program_id);
+            host.events.push(EmittedEvent {
+                program_id,
+                topic: topic.to_string(),
+                payload,
+            });
+        });
+        0
+    }
+
+    pub(crate) unsafe fn _savepoint() -> i64 {
+        with_host(|host| host.savepoint())
+    }
+
+    pub(crate) unsafe fn _revert(savepoint: i64) -> i32 {
+        with_host(|host| match host.revert(savepoint) {
+            true => 0,
+            false => HOST_ERR,
+        })
+    }
+
+    /// Calls the program following the protocol of `InvokeModule.invoke` in
+    /// x/programs/runtime/program_invoke.go.
+    pub(crate) unsafe fn _invoke_program(
+        _contract_id: u64,
+        call_contract_id: u64,
+        method_name_ptr: *const u8,
+        method_name_len: usize,
+        args_ptr: *const u8,
+        args_len: usize,
+    ) -> i64 {
+        let Ok(method) = std::str::from_utf8(read(method_name_ptr, method_name_len)) else {
+            return i64::from(HOST_ERR);
+        };
+        let Some(entry) = with_host(|host| host.code.get(&call_contract_id).cloned()) else {
+            return i64::from(HOST_ERR);
+        };
+        // the callee could not run a single instruction
+        if with_host(|host| host.units == Some(0)) {
+            return INVOKE_INSUFFICIENT_UNITS;
+        }
+
+        let callee = ProgramContext::from(call_contract_id as i64);
+        let mut args = CallArgs::encode(&[ProgramValue::ProgramObject(callee)]);
+        args.extend_from_slice(read(args_ptr, args_len));
+        // handed over to the callee, which frees it
+        let args_len = args.len();
+        let args_ptr = Box::into_raw(args.into_boxed_slice()) as *mut u8;
+
+        let savepoint = with_host(|host| host.savepoint());
+        with_host(|host| {
+            // the id passed is never trusted, functions called directly run for the actor
+            let caller = host.frames.last().map_or(0, |frame| frame.program_id);
+            host.frames.push(Frame {
+                program_id: call_contract_id,
+                caller,
+                result: None,
+                error: 0,
+            });
+        });
+        let status = match entry {
+            Entry::Dispatch(dispatch) => dispatch(method_id(method), args_ptr, args_len),
+            Entry::Methods(methods) => match methods(method, args_ptr, args_len) {
+                true => 0,
+                false => {
+                    drop(Box::from_raw(std::ptr::slice_from_raw_parts_mut(
+                        args_ptr, args_len,
+                    )));
+                    INVOKE_UNKNOWN_METHOD
+                }
+            },
+        };
+        let frame = with_host(|host| host.frames.pop()).expect("pushed above");
+
+        let result = match frame.result {
+            _ if frame.error != 0 => Err(frame.error),
+            _ if status < 0 => Err(status),
+            Some(result) => Ok(result),
+            None => Err(i64::from(HOST_ERR)),
+        };
+        match result {
+            Ok(result) => {
+                let len = result.len() as i64;
+                (give_buffer(result) as i64) << 32 | len
+            }
+            Err(status) => {
+                with_host(|host| host.revert(savepoint));
+                status
+            }
+        }
+    }
+
+    pub(crate) unsafe fn _invoke_program_with_units(
+        contract_id: u64,
+        call_contract_id: u64,
+        method_name_ptr: *const u8,
+        method_name_len: usize,
+        args_ptr: *const u8,
+        args_len: usize,
+        max_units: u64,
+    ) -> i64 {
+        // the callee sees at most its limit remaining, and consumes nothing natively
+        let units = with_host(|host| host.units);
+        let limit = units.map_or(max_units, |units| units.min(max_units));
+        with_host(|host| host.units = Some(limit));
+        let result = _invoke_program(
+            contract_id,
+            call_contract_id,
+            method_name_ptr,
+            method_name_len,
+            args_ptr,
+            args_len,
+        );
+        with_host(|host| host.units = units);
+        result
+    }
+
+    pub(crate) unsafe fn _set_call_result(result_ptr: *const u8, result_len: usize) -> i32 {
+        let result = read(result_ptr, result_len).to_vec();
+        with_host(|host| match host.frames.last_mut() {
+            Some(frame) => {
+                frame.result = Some(result);
+                0
+            }
+            // called directly rather than through the host, so there is no caller to return to
+            None => 0,
+        })
+    }
+
+    pub(crate) unsafe fn _set_call_error(code: i64) -> i32 {
+        // only failure codes can be set
+        if code >= 0 {
+            return HOST_ERR;
+        }
+        with_host(|host| {
+            if let Some(frame) = host.frames.last_mut() {
+                frame.error = code;
+            }
+        });
+        0
+    }
+
+    pub(crate) unsafe fn _actor(actor_ptr: *const u8) -> i32 {
+        match with_host(|host| host.actor) {
+            Some(actor) => {
+                write(actor_ptr, actor.as_bytes());
+                0
+            }
+            None => HOST_ERR,
+        }
+    }
+
+    pub(crate) unsafe fn _caller() -> i64 {
+        with_host(|host| host.frames.last().map_or(0, |frame| frame.caller as i64))
+    }
+
+    pub(crate) unsafe fn _remaining_units() -> i64 {
+        with_host(|host| host.units.unwrap_or(u64::MAX) as i64)
+    }
+
+    pub(crate) unsafe fn _height() -> i64 {
+        crate::env::mock::get().height as i64
+    }
+
+    pub(crate) unsafe fn _timestamp() -> i64 {
+        crate::env::mock::get().timestamp as i64
+    }
+
+    pub(crate) unsafe fn _chain_id(id_ptr: *const u8) -> i32 {
+        write(id_ptr, crate::env::mock::get().chain_id.as_bytes());
+        0
+    }
+
+    pub(crate) unsafe fn _tx_id(id_ptr: *const u8) -> i32 {
+        write(id_ptr, crate::env::mock::get().tx_id.as_bytes());
+        0
+    }
+}
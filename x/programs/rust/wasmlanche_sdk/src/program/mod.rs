@@ -1,15 +1,25 @@
-use crate::errors::StorageError;
-use crate::host::init_program_storage;
-use crate::store::{to_string, ProgramContext, Store, Tag};
-use crate::types::Address;
+use crate::codec::{Packer, Unpacker};
+use crate::errors::{CallError, EventError, MathError, StorageError};
+use crate::store::{
+    get_reserved_key, has_key, store_key_value, to_string, ProgramContext, Store, Tag,
+};
+use crate::types::{Address, U256};
 use std::borrow::Cow;
-use std::collections::HashMap;
 use thiserror::Error;
 
 #[derive(Clone, Error, Debug)]
 pub enum ProgramError {
     #[error("{0}")]
     Store(#[from] StorageError),
+
+    #[error("{0}")]
+    Call(#[from] CallError),
+
+    #[error("{0}")]
+    Math(#[from] MathError),
+
+    #[error("{0}")]
+    Event(#[from] EventError),
 }
 
 /// ProgramValue represents a value that can be stored in the host.
@@ -20,38 +30,10 @@ pub enum ProgramValue {
     IntObject(i64),
     AddressObject(Address),
     ProgramObject(ProgramContext),
-}
-
-/// Program represents a program and its associated fields.
-pub struct Program {
-    fields: HashMap<String, ProgramValue>,
-}
-
-impl Default for Program {
-    fn default() -> Self {
-        Self::new()
-    }
-}
-
-impl Program {
-    pub fn new() -> Self {
-        Program {
-            fields: HashMap::new(),
-        }
-    }
-    pub fn add_field(&mut self, name: String, val: ProgramValue) {
-        self.fields.insert(name, val);
-    }
-    /// Initializes all the fields in the program and stores them in the host.
-    pub fn publish(self) -> Result<ProgramContext, ProgramError> {
-        // get the program_id from the host
-        let ctx: ProgramContext = init_program_storage();
-        // iterate through fields an set them in the host
-        for (key, value) in &self.fields {
-            ctx.store_value(key, value)?;
-        }
-        Ok(ctx)
-    }
+    U64Object(u64),
+    U128Object(u128),
+    I128Object(i128),
+    U256Object(U256),
 }
 
 /// All program values implement Store. This allows us to store them in the host.
@@ -72,6 +54,10 @@ impl Store for ProgramValue {
                 // Since ProgramContext is a wrapper around a u64
                 Cow::Owned(val.program_id.to_be_bytes().to_vec())
             }
+            ProgramValue::U64Object(val) => Cow::Owned(val.to_be_bytes().to_vec()),
+            ProgramValue::U128Object(val) => Cow::Owned(val.to_be_bytes().to_vec()),
+            ProgramValue::I128Object(val) => Cow::Owned(val.to_be_bytes().to_vec()),
+            ProgramValue::U256Object(val) => Cow::Owned(val.to_be_bytes().to_vec()),
         }
     }
 
@@ -86,18 +72,19 @@ impl Store for ProgramValue {
         }
         // First byte must represent the "tag" of the ProgramValue.
         let tag = Tag::from(bytes[0]);
+        let tagged = bytes;
         let bytes = &bytes[1..];
-        match tag.0 {
-            1 => match to_string(bytes.to_vec()) {
+        match tag {
+            Tag::STRING => match to_string(bytes.to_vec()) {
                 Ok(val) => Ok(ProgramValue::StringObject(val)),
                 Err(_) => Err(StorageError::InvalidBytes()),
             },
-            2 => Ok(ProgramValue::MapObject),
-            3 => {
+            Tag::MAP => Ok(ProgramValue::MapObject),
+            Tag::INT => {
                 let num = int_from_bytes(bytes)?;
                 Ok(ProgramValue::IntObject(num))
             }
-            4 => {
+            Tag::ADDRESS => {
                 let address_bytes: [u8; 32] = match bytes.try_into() {
                     Ok(val) => val,
                     Err(_) => {
@@ -107,27 +94,187 @@ impl Store for ProgramValue {
 
                 Ok(ProgramValue::AddressObject(Address::new(address_bytes)))
             }
-            5 => {
+            Tag::PROGRAM => {
                 let num = int_from_bytes(bytes)?;
                 Ok(ProgramValue::ProgramObject(ProgramContext::from(num)))
             }
-            invalid_tag => Err(StorageError::InvalidTag(invalid_tag)),
+            // the wide integers share the layout of their Store implementations
+            Tag::U64 => u64::from_bytes(tagged).map(ProgramValue::U64Object),
+            Tag::U128 => u128::from_bytes(tagged).map(ProgramValue::U128Object),
+            Tag::I128 => i128::from_bytes(tagged).map(ProgramValue::I128Object),
+            Tag::U256 => U256::from_bytes(tagged).map(ProgramValue::U256Object),
+            Tag(invalid_tag) => Err(StorageError::InvalidTag(invalid_tag)),
         }
     }
 
     /// The tag is used to identify the type of the value, and is prepended when storing in a map.
     fn as_tag(&self) -> Tag {
         match self {
-            ProgramValue::StringObject(_) => Tag(1),
-            ProgramValue::MapObject => Tag(2),
-            ProgramValue::IntObject(_) => Tag(3),
-            ProgramValue::AddressObject(_) => Tag(4),
-            ProgramValue::ProgramObject(_) => Tag(5),
+            ProgramValue::StringObject(_) => Tag::STRING,
+            ProgramValue::MapObject => Tag::MAP,
+            ProgramValue::IntObject(_) => Tag::INT,
+            ProgramValue::AddressObject(_) => Tag::ADDRESS,
+            ProgramValue::ProgramObject(_) => Tag::PROGRAM,
+            ProgramValue::U64Object(_) => Tag::U64,
+            ProgramValue::U128Object(_) => Tag::U128,
+            ProgramValue::I128Object(_) => Tag::I128,
+            ProgramValue::U256Object(_) => Tag::U256,
         }
     }
 }
 
-fn int_from_bytes(bytes: &[u8]) -> Result<i64, StorageError> {
+/// CallArgs reads the parameters of an exposed function from the argument buffer built by
+/// [CallArgs::encode]. Each argument is a big endian i64 length, a byte set
+/// to 1 for ints, and the tagged bytes of the argument.
+pub struct CallArgs {
+    bytes: Vec<u8>,
+    offset: usize,
+}
+
+impl CallArgs {
+    /// The size of the length and int flag written before each argument.
+    const META_SIZE: usize = std::mem::size_of::<i64>() + 1;
+
+    pub fn new(bytes: Vec<u8>) -> Self {
+        Self { bytes, offset: 0 }
+    }
+
+    /// Encodes [args] into the buffer read by [CallArgs::next_arg].
+    pub fn encode(args: &[ProgramValue]) -> Vec<u8> {
+        let args: Vec<_> = args
+            .iter()
+            .map(|arg| {
+                (
+                    matches!(
+                        arg,
+                        ProgramValue::IntObject(_)
+                            | ProgramValue::U64Object(_)
+                            | ProgramValue::U128Object(_)
+                            | ProgramValue::I128Object(_)
+                            | ProgramValue::U256Object(_)
+                    ),
+                    arg.to_tagged_bytes(),
+                )
+            })
+            .collect();
+
+        // Calculate the total size of the combined byte slices
+        let total_size: usize = args
+            .iter()
+            .map(|(_, arg)| arg.len() + Self::META_SIZE)
+            .sum();
+
+        // Create a mutable Vec<u8> to hold the combined bytes
+        let mut bytes = Vec::with_capacity(total_size);
+
+        for (is_int, arg) in args {
+            let len = i64::try_from(arg.len()).expect("Not handling errors yet.");
+            bytes.extend_from_slice(&len.to_be_bytes());
+            bytes.push(u8::from(is_int));
+            // the tag lets the callee check each argument has the expected type
+            bytes.extend_from_slice(&arg);
+        }
+        bytes
+    }
+
+    /// Takes ownership of an argument buffer the host allocated in the module.
+    ///
+    /// # Safety
+    /// [ptr] must point to a buffer of [len] bytes allocated with [crate::host::alloc],
+    /// which is not used again by the caller.
+    pub unsafe fn from_host(ptr: *mut u8, len: usize) -> Self {
+        if len == 0 {
+            return Self::new(Vec::new());
+        }
+        Self::new(Vec::from_raw_parts(ptr, len, len))
+    }
+
+    /// Decodes the next argument as [T].
+    pub fn next_arg<T: Store>(&mut self) -> Result<T, StorageError> {
+        let rest = &self.bytes[self.offset..];
+        if rest.len() < Self::META_SIZE {
+            return Err(StorageError::InvalidByteLength(rest.len()));
+        }
+        let len = int_from_bytes(&rest[..std::mem::size_of::<i64>()])?;
+        let value = usize::try_from(len)
+            .ok()
+            .and_then(|len| rest[Self::META_SIZE..].get(..len))
+            .ok_or(StorageError::InvalidByteLength(rest.len()))?;
+        self.offset += Self::META_SIZE + value.len();
+        T::from_bytes(value)
+    }
+
+    /// Ensures every argument has been read.
+    pub fn finish(self) -> Result<(), StorageError> {
+        match self.bytes.len() - self.offset {
+            0 => Ok(()),
+            len => Err(StorageError::InvalidByteLength(len)),
+        }
+    }
+}
+
+/// The reserved key marking a program whose `#[init]` method ran, which the program itself
+/// can not store or remove.
+const INITIALIZED_KEY: &str = "initialized";
+
+/// Marks the program [ctx] as initialized by the `dispatch` function generated by
+/// `#[program]`. Returns false if it already was.
+pub fn mark_initialized(ctx: &ProgramContext) -> Result<bool, StorageError> {
+    let key = get_reserved_key(INITIALIZED_KEY);
+    if has_key(ctx, &key)? {
+        return Ok(false);
+    }
+    store_key_value(ctx, &key, &true)?;
+    Ok(true)
+}
+
+/// The result of calling the `#[init]` method of a program built with `#[program]`: the
+/// program it created and the value the method returned. It has the layout `Storable`
+/// derives for a struct of these two fields.
+#[derive(Clone)]
+pub struct Initialized<T> {
+    pub program: ProgramContext,
+    pub result: T,
+}
+
+impl<T: Store> Store for Initialized<T> {
+    fn as_bytes(&self) -> Cow<'_, [u8]> {
+        let mut packer = Packer::new(0);
+        packer.pack(&self.program);
+        packer.pack(&self.result);
+        Cow::Owned(packer.finish())
+    }
+
+    fn as_tag(&self) -> Tag {
+        Tag::STRUCT
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self, StorageError> {
+        let mut unpacker = Unpacker::new(Tag::STRUCT.strip(bytes)?, 0)?;
+        let initialized = Self {
+            program: unpacker.unpack()?,
+            result: unpacker.unpack()?,
+        };
+        unpacker.finish()?;
+        Ok(initialized)
+    }
+}
+
+/// Returns the selector the `dispatch` function generated by `#[program]` uses for the
+/// method [name], which is the 32 bit FNV-1a hash of the name.
+pub const fn method_id(name: &str) -> u32 {
+    let bytes = name.as_bytes();
+    let mut hash: u32 = 0x811c_9dc5;
+    let mut i = 0;
+    while i < bytes.len() {
+        hash ^= bytes[i] as u32;
+        hash = hash.wrapping_mul(0x0100_0193);
+        i += 1;
+    }
+    hash
+}
+
+pub(crate) fn int_from_bytes(bytes: &[u8]) -> Result<i64, StorageError> {
     match bytes.len() {
         8 => (),
         len => return Err(StorageError::InvalidByteLength(len)),
@@ -1,15 +1,56 @@
-use crate::errors::StorageError;
-use crate::host::{get_bytes, get_bytes_len, host_program_invoke, store_bytes};
-use crate::program::ProgramValue;
+use crate::collections::Page;
+use crate::errors::{CallError, EventError, StorageError};
+use crate::event::Event;
+use crate::host::{
+    self, delete_bytes, get_bytes, get_bytes_len, host_program_invoke,
+    host_program_invoke_with_units, iter_prefix, store_bytes, take_host_buffer, MAP_NOT_FOUND,
+};
+use crate::program::{CallArgs, ProgramValue};
+use crate::types::Address;
 use std::borrow::Cow;
 use std::str;
 
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub struct Tag(pub u8);
 
 impl Tag {
+    pub const UNIT: Tag = Tag(0);
+    pub const STRING: Tag = Tag(1);
+    pub const MAP: Tag = Tag(2);
+    pub const INT: Tag = Tag(3);
+    pub const ADDRESS: Tag = Tag(4);
+    pub const PROGRAM: Tag = Tag(5);
+    pub const BOOL: Tag = Tag(6);
+    /// Tag shared by all structs deriving `Storable`.
+    pub const STRUCT: Tag = Tag(7);
+    /// Tag shared by all enums deriving `Storable`.
+    pub const ENUM: Tag = Tag(8);
+    pub const I32: Tag = Tag(9);
+    /// Tag of an `Option`, followed by 0 for None or 1 and the tagged value for Some.
+    pub const OPTION: Tag = Tag(10);
+    /// Tag of a `Result`, followed by 0 and the tagged value for Ok or 1 and the tagged error for Err.
+    pub const RESULT: Tag = Tag(11);
+    pub const U64: Tag = Tag(12);
+    pub const U128: Tag = Tag(13);
+    pub const I128: Tag = Tag(14);
+    /// Tag of a [crate::types::U256].
+    pub const U256: Tag = Tag(15);
+
     pub fn as_u8(&self) -> u8 {
         self.0
     }
+
+    /// Checks that the first byte of [bytes] is this tag and returns the remaining bytes.
+    pub fn strip<'a>(&self, bytes: &'a [u8]) -> Result<&'a [u8], StorageError> {
+        match bytes.split_first() {
+            Some((tag, rest)) if *tag == self.0 => Ok(rest),
+            Some((tag, _)) => Err(StorageError::TypeMismatch {
+                expected: self.0,
+                found: *tag,
+            }),
+            None => Err(StorageError::InvalidByteLength(0)),
+        }
+    }
 }
 
 impl From<u8> for Tag {
@@ -19,10 +60,20 @@ impl From<u8> for Tag {
 }
 
 /// Store represents any type that can be stored in the host.
+///
+/// Values are stored as their tag followed by [Store::as_bytes], so [Store::from_bytes]
+/// always receives the tagged bytes. Implement it for your own structs and enums
+/// with `#[derive(Storable)]`.
 pub trait Store {
     fn as_bytes(&self) -> Cow<'_, [u8]>;
     /// Returns the tag of the type.
     fn as_tag(&self) -> Tag;
+    /// Returns the tag followed by the bytes of the value.
+    fn to_tagged_bytes(&self) -> Vec<u8> {
+        std::iter::once(self.as_tag().as_u8())
+            .chain(self.as_bytes().iter().copied())
+            .collect()
+    }
     fn from_bytes(bytes: &[u8]) -> Result<Self, StorageError>
     where
         Self: Sized;
@@ -37,9 +88,9 @@ pub struct ProgramContext {
 
 impl ProgramContext {
     pub fn store_value<T: Store>(&self, key: &str, value: &T) -> Result<(), StorageError> {
-        let key_bytes = key.as_bytes();
+        let key_bytes = get_field_key(key);
         // Add the tag(u8) to the start of val_bytes
-        store_key_value(self, key_bytes.to_vec(), value)
+        store_key_value(self, &key_bytes, value)
     }
 
     pub fn store_map_value<T: Store>(
@@ -50,17 +101,82 @@ impl ProgramContext {
     ) -> Result<(), StorageError> {
         let key_bytes = get_map_key(map_name, &key);
         // Add a tag(u8) to the start of val_bytes
-        store_key_value(self, key_bytes, &value)
+        store_key_value(self, &key_bytes, &value)
     }
-    pub fn get_value(&self, name: &str) -> Result<ProgramValue, StorageError> {
+    /// Gets the value stored at [name], or None if nothing is stored there.
+    /// Returns a `TypeMismatch` error if the stored value is not a [T].
+    pub fn get_value<T: Store>(&self, name: &str) -> Result<Option<T>, StorageError> {
         get_field(self, name)
     }
-    pub fn get_map_value(
+    /// Gets the value stored at [key] in the map [map_name], or None if nothing is stored there.
+    /// Returns a `TypeMismatch` error if the stored value is not a [T].
+    pub fn get_map_value<T: Store>(
         &self,
         map_name: &str,
         key: ProgramValue,
-    ) -> Result<ProgramValue, StorageError> {
-        get_map_field(self, map_name, key)
+    ) -> Result<Option<T>, StorageError> {
+        get_map_field(self, map_name, &key)
+    }
+    /// Removes the value stored at [name] and returns it, or None if nothing was stored there.
+    pub fn remove_value<T: Store>(&self, name: &str) -> Result<Option<T>, StorageError> {
+        remove_key(self, &get_field_key(name))
+    }
+    /// Removes the value stored at [key] in the map [map_name] and returns it,
+    /// or None if nothing was stored there.
+    pub fn remove_map_value<T: Store>(
+        &self,
+        map_name: &str,
+        key: ProgramValue,
+    ) -> Result<Option<T>, StorageError> {
+        remove_key(self, &get_map_key(map_name, &key))
+    }
+    /// Reads up to [limit] entries whose host keys start with [prefix], in ascending key
+    /// order, as raw keys and tagged values. Only keys >= [start] and < [end] are read, and
+    /// an empty bound is unbounded.
+    pub fn iter_prefix(
+        &self,
+        prefix: &[u8],
+        start: &[u8],
+        end: &[u8],
+        limit: u32,
+    ) -> Result<Vec<RawEntry>, StorageError> {
+        get_entries_with_prefix(self, prefix, start, end, limit)
+    }
+    /// Reads up to [limit] entries of the map [map_name] ordered by their encoded keys,
+    /// starting at [start] (inclusive) and stopping before [end] when they are set.
+    /// Pass the returned `Page::next` as [start] to read the following page.
+    /// Returns an `InvalidLimit` error if [limit] is 0, as the page could not advance.
+    pub fn iter_map<K: Store, V: Store>(
+        &self,
+        map_name: &str,
+        start: Option<&K>,
+        end: Option<&K>,
+        limit: u32,
+    ) -> Result<Page<K, V>, StorageError> {
+        if limit == 0 {
+            return Err(StorageError::InvalidLimit(limit));
+        }
+        let prefix = get_map_prefix(map_name);
+        let start = start.map(|key| get_map_key(map_name, key));
+        let end = end.map(|key| get_map_key(map_name, key));
+        // Read one extra entry to learn where the next page starts.
+        let mut entries = get_entries_with_prefix(
+            self,
+            &prefix,
+            start.as_deref().unwrap_or_default(),
+            end.as_deref().unwrap_or_default(),
+            limit.saturating_add(1),
+        )?
+        .into_iter()
+        .map(|(key, value)| Ok((decode_map_key(&prefix, &key)?, V::from_bytes(&value)?)))
+        .collect::<Result<Vec<(K, V)>, StorageError>>()?;
+
+        let next = if entries.len() > limit as usize {
+            entries.pop().map(|(key, _)| key)
+        } else {
+            None
+        };
+        Ok(Page { entries, next })
     }
 }
 
@@ -78,14 +194,12 @@ impl From<i64> for ProgramContext {
     }
 }
 
-fn store_key_value<T: Store>(
+pub(crate) fn store_key_value<T: Store>(
     ctx: &ProgramContext,
-    key_bytes: Vec<u8>,
+    key_bytes: &[u8],
     value: &T,
 ) -> Result<(), StorageError> {
-    let val_bytes = std::iter::once(value.as_tag().as_u8())
-        .chain(value.as_bytes().iter().copied())
-        .collect::<Vec<u8>>();
+    let val_bytes = value.to_tagged_bytes();
     match unsafe {
         store_bytes(
             ctx,
@@ -96,30 +210,101 @@ fn store_key_value<T: Store>(
         )
     } {
         0 => Ok(()),
-        _ => Err(StorageError::HostStoreError()),
+        code => Err(StorageError::HostStoreError(code)),
     }
 }
 
-fn get_field_as_bytes(ctx: &ProgramContext, name: &[u8]) -> Result<Vec<u8>, StorageError> {
+pub(crate) fn get_field_as_bytes(
+    ctx: &ProgramContext,
+    name: &[u8],
+) -> Result<Option<Vec<u8>>, StorageError> {
     let name_ptr = name.as_ptr();
     let name_len = name.len();
     // First get the length of the bytes from the host.
-    let bytes_len = unsafe { get_bytes_len(ctx, name_ptr, name_len) };
-    // Speculation that compiler might be optimizing out this if statement.
-    if bytes_len < 0 {
-        return Err(StorageError::InvalidByteLength(bytes_len as usize));
-    }
+    let bytes_len = match unsafe { get_bytes_len(ctx, name_ptr, name_len) } {
+        MAP_NOT_FOUND => return Ok(None),
+        code if code < 0 => return Err(StorageError::HostRetrieveError(code)),
+        len => len,
+    };
     // Get_bytes allocates bytes_len memory in the WASM module.
     let bytes_ptr = unsafe { get_bytes(ctx, name_ptr, name_len, bytes_len) };
     // Defensive check here to unsure we don't grab out of bounds memory.
     if bytes_ptr < 0 {
-        return Err(StorageError::HostRetrieveError());
+        return Err(StorageError::HostRetrieveError(bytes_ptr));
     }
-    let bytes_ptr = bytes_ptr as *mut u8;
-
     // Take ownership of those bytes grabbed from the host. We want Rust to manage the memory.
-    let bytes = unsafe { Vec::from_raw_parts(bytes_ptr, bytes_len as usize, bytes_len as usize) };
-    Ok(bytes)
+    let bytes = unsafe { take_host_buffer(bytes_ptr as usize, bytes_len as usize) };
+    Ok(Some(bytes))
+}
+
+/// Returns whether a value is stored at [key] in the host.
+pub(crate) fn has_key(ctx: &ProgramContext, key: &[u8]) -> Result<bool, StorageError> {
+    match unsafe { get_bytes_len(ctx, key.as_ptr(), key.len()) } {
+        MAP_NOT_FOUND => Ok(false),
+        code if code < 0 => Err(StorageError::HostRetrieveError(code)),
+        _ => Ok(true),
+    }
+}
+
+/// Deletes the value stored at [key] in the host. Deleting a missing key is not an error.
+pub(crate) fn delete_key(ctx: &ProgramContext, key: &[u8]) -> Result<(), StorageError> {
+    match unsafe { delete_bytes(ctx, key.as_ptr(), key.len()) } {
+        0 | MAP_NOT_FOUND => Ok(()),
+        code => Err(StorageError::HostDeleteError(code)),
+    }
+}
+
+/// Deletes the value stored at [key] in the host and returns it decoded as [T].
+/// Nothing is deleted if the stored value is not a [T].
+pub(crate) fn remove_key<T: Store>(
+    ctx: &ProgramContext,
+    key: &[u8],
+) -> Result<Option<T>, StorageError> {
+    let Some(bytes) = get_field_as_bytes(ctx, key)? else {
+        return Ok(None);
+    };
+    let value = T::from_bytes(&bytes)?;
+    delete_key(ctx, key)?;
+    Ok(Some(value))
+}
+
+/// A key and its tagged value as stored in the host.
+pub type RawEntry = (Vec<u8>, Vec<u8>);
+
+/// Reads up to [limit] raw key-value pairs whose keys start with [prefix] from the host.
+/// Empty [start] and [end] bounds are unbounded.
+pub(crate) fn get_entries_with_prefix(
+    ctx: &ProgramContext,
+    prefix: &[u8],
+    start: &[u8],
+    end: &[u8],
+    limit: u32,
+) -> Result<Vec<RawEntry>, StorageError> {
+    let packed = iter_prefix(ctx, prefix, start, end, limit);
+    if packed < 0 {
+        return Err(StorageError::HostRetrieveError(packed as i32));
+    }
+    let bytes = unsafe { take_packed_buffer(packed) };
+    let mut rest = bytes.as_slice();
+    let mut entries = Vec::new();
+    while !rest.is_empty() {
+        let (key, after_key) = split_key_segment(rest)?;
+        let (value, after_value) = split_key_segment(after_key)?;
+        entries.push((key.to_vec(), value.to_vec()));
+        rest = after_value;
+    }
+    Ok(entries)
+}
+
+/// Takes ownership of a buffer the host allocated in the module and described with its
+/// offset in the upper 32 bits of [packed] and its length in the lower 32 bits.
+///
+/// # Safety
+/// [packed] must describe a buffer allocated with `alloc` that nothing else owns.
+unsafe fn take_packed_buffer(packed: i64) -> Vec<u8> {
+    let bytes_ptr = (packed >> 32) as usize;
+    let bytes_len = (packed & 0xFFFF_FFFF) as usize;
+    take_host_buffer(bytes_ptr, bytes_len)
 }
 
 /// Converts a byte vector to a string
@@ -127,74 +312,196 @@ pub fn to_string(bytes: Vec<u8>) -> Result<String, std::string::FromUtf8Error> {
     String::from_utf8(bytes)
 }
 
-/// Gets the field [name] from the host and returns it as a ProgramValue.
-fn get_field<T: Store>(ctx: &ProgramContext, name: &str) -> Result<T, StorageError> {
-    let bytes = get_field_as_bytes(ctx, name.as_bytes())?;
-    T::from_bytes(&bytes)
+/// Gets the field [name] from the host and decodes it as [T].
+fn get_field<T: Store>(ctx: &ProgramContext, name: &str) -> Result<Option<T>, StorageError> {
+    get_field_as_bytes(ctx, &get_field_key(name))?
+        .map(|bytes| T::from_bytes(&bytes))
+        .transpose()
+}
+
+/// The first byte of every host storage key, identifying what kind of value it refers to.
+/// This keeps top level fields and map entries from ever sharing a key.
+#[repr(u8)]
+enum KeyKind {
+    Field = 0,
+    Map = 1,
+    /// Values the SDK stores for itself, which the fields and maps of a program can not reach.
+    Reserved = 2,
+}
+
+/// Appends [segment] to [key] prefixed with its length as a big endian u32, so that
+/// adjacent segments cannot run into each other.
+fn push_key_segment(key: &mut Vec<u8>, segment: &[u8]) {
+    let len = u32::try_from(segment.len()).expect("key segment exceeds u32::MAX bytes");
+    key.extend_from_slice(&len.to_be_bytes());
+    key.extend_from_slice(segment);
 }
 
-/// Gets the correct key to in the host storage for a [map_name] and [key] within that map  
-fn get_map_key(map_name: &str, key: &ProgramValue) -> Vec<u8> {
-    [map_name.as_bytes(), &key.as_bytes()].concat()
+/// Splits a segment written by [push_key_segment] from the front of [bytes].
+fn split_key_segment(bytes: &[u8]) -> Result<(&[u8], &[u8]), StorageError> {
+    if bytes.len() < std::mem::size_of::<u32>() {
+        return Err(StorageError::InvalidByteLength(bytes.len()));
+    }
+    let (len, rest) = bytes.split_at(std::mem::size_of::<u32>());
+    let len = u32::from_be_bytes(len.try_into().expect("4 byte length")) as usize;
+    if rest.len() < len {
+        return Err(StorageError::InvalidByteLength(len));
+    }
+    Ok(rest.split_at(len))
+}
+
+/// Gets the key in the host storage for the top level field [name].
+pub(crate) fn get_field_key(name: &str) -> Vec<u8> {
+    let mut key = vec![KeyKind::Field as u8];
+    push_key_segment(&mut key, name.as_bytes());
+    key
+}
+
+/// Gets the key in the host storage for the value [name] reserved by the SDK.
+pub(crate) fn get_reserved_key(name: &str) -> Vec<u8> {
+    let mut key = vec![KeyKind::Reserved as u8];
+    push_key_segment(&mut key, name.as_bytes());
+    key
 }
 
-// Gets the value from the map [name] with key [key] from the host and returns it as a ProgramValue.
-fn get_map_field<T: Store>(
+/// Gets the prefix shared by the keys of every entry in the map [map_name].
+pub(crate) fn get_map_prefix(map_name: &str) -> Vec<u8> {
+    let mut key = vec![KeyKind::Map as u8];
+    push_key_segment(&mut key, map_name.as_bytes());
+    key
+}
+
+/// Gets the correct key to in the host storage for a [map_name] and [key] within that map.
+/// The tag of [key] is included so keys of different types never collide.
+pub(crate) fn get_map_key<K: Store>(map_name: &str, key: &K) -> Vec<u8> {
+    let mut map_key = get_map_prefix(map_name);
+    push_key_segment(&mut map_key, &key.to_tagged_bytes());
+    map_key
+}
+
+/// Decodes the map key of an entry whose host storage key is [map_key].
+fn decode_map_key<K: Store>(prefix: &[u8], map_key: &[u8]) -> Result<K, StorageError> {
+    let key_bytes = map_key
+        .strip_prefix(prefix)
+        .ok_or(StorageError::InvalidBytes())?;
+    match split_key_segment(key_bytes)? {
+        (key, []) => K::from_bytes(key),
+        (_, rest) => Err(StorageError::InvalidByteLength(rest.len())),
+    }
+}
+
+// Gets the value from the map [name] with key [key] from the host and decodes it as [T].
+pub(crate) fn get_map_field<K: Store, T: Store>(
     ctx: &ProgramContext,
     name: &str,
-    key: ProgramValue,
-) -> Result<T, StorageError> {
-    let map_key = get_map_key(name, &key);
-    let map_value = get_field_as_bytes(ctx, &map_key)?;
-    T::from_bytes(&map_value)
+    key: &K,
+) -> Result<Option<T>, StorageError> {
+    let map_key = get_map_key(name, key);
+    get_field_as_bytes(ctx, &map_key)?
+        .map(|bytes| T::from_bytes(&bytes))
+        .transpose()
+}
+
+/// Identities the host provides to the running program, for authorization checks.
+impl ProgramContext {
+    /// Returns the address of the transaction signer, which is the same for every program
+    /// in a chain of program_invoke calls.
+    ///
+    /// # Panics
+    /// Panics, failing the call, if the host did not provide an actor.
+    pub fn actor(&self) -> Address {
+        host::actor().expect("the host did not provide an actor")
+    }
+
+    /// Returns the program that invoked this one with program_invoke, or None if the
+    /// actor called it directly.
+    pub fn caller(&self) -> Option<ProgramContext> {
+        host::caller()
+    }
+}
+
+/// Implement event emission for the ProgramContext.
+impl ProgramContext {
+    /// Emits [event] from this program, so indexers learn what the program did.
+    pub fn emit_event<E: Event>(&self, event: &E) -> Result<(), EventError> {
+        match host::emit_event(self, E::TOPIC, &event.payload()) {
+            0 => Ok(()),
+            code => Err(EventError::Host(code)),
+        }
+    }
+}
+
+/// Implement atomic updates for the ProgramContext, so an update that fails partway leaves
+/// no partial state behind.
+impl ProgramContext {
+    /// Runs [f], discarding every value it stored or deleted and every event it emitted,
+    /// including those of the programs it invoked, if it returns an error.
+    pub fn atomic<T, E>(&self, f: impl FnOnce(&Self) -> Result<T, E>) -> Result<T, E> {
+        atomic(|| f(self))
+    }
+}
+
+/// Runs [f] like [ProgramContext::atomic], for code without a context such as the wrappers
+/// generated by the expose macro.
+///
+/// # Panics
+/// Panics if the host can not revert to the savepoint, so the host discards the changes of
+/// the whole call rather than leaving [f]'s behind.
+pub fn atomic<T, E>(f: impl FnOnce() -> Result<T, E>) -> Result<T, E> {
+    let savepoint = host::savepoint();
+    let result = f();
+    if result.is_err() && host::revert(savepoint) != 0 {
+        panic!("failed to revert to savepoint {savepoint}");
+    }
+    result
 }
 
 /// Implement the program_invoke function for the ProgramContext which allows a program to
 /// call another program.
 impl ProgramContext {
-    pub fn program_invoke(
+    /// Calls [fn_name] on the program [call_ctx] and decodes the tagged value it returned as [R].
+    pub fn program_invoke<R: Store>(
         &self,
         call_ctx: &ProgramContext,
         fn_name: &str,
         call_args: &[ProgramValue],
-    ) -> ProgramValue {
-        // hardcode first arg for now
-        let result = host_program_invoke(self, call_ctx, fn_name, &Self::marshal_args(call_args));
-        // Hardcode int for now
-        ProgramValue::IntObject(result)
-    }
-
-    fn marshal_args(args: &[ProgramValue]) -> Vec<u8> {
-        use std::mem::size_of;
-        // Size of meta data for each argument
-        let meta_size = size_of::<i64>() + 1;
-
-        // Calculate the total size of the combined byte slices
-        let total_size: usize = args
-            .iter()
-            .map(|cow| cow.as_bytes().len() + meta_size)
-            .sum();
-
-        // Create a mutable Vec<u8> to hold the combined bytes
-        let mut bytes = Vec::with_capacity(total_size);
-
-        for arg in args {
-            // if we want to be efficient we dont need to add length of bytes if its an int
-            let len = ProgramValue::IntObject(
-                i64::try_from(arg.as_bytes().len()).expect("Not handling errors yet."),
-            );
-            bytes.extend_from_slice(&len.as_bytes());
-
-            match arg {
-                ProgramValue::IntObject(_) => {
-                    bytes.extend_from_slice(&[1]);
-                }
-                _ => {
-                    bytes.extend_from_slice(&[0]);
-                }
-            }
-            bytes.extend_from_slice(&arg.as_bytes());
-        }
-        bytes
+    ) -> Result<R, CallError> {
+        let result = host_program_invoke(self, call_ctx, fn_name, &CallArgs::encode(call_args));
+        unsafe { take_call_result(result) }
+    }
+
+    /// Calls [fn_name] on the program [call_ctx] like [ProgramContext::program_invoke], failing
+    /// with [CallError::InsufficientUnits] if the callee consumes more than [max_units]. Only the
+    /// units the callee consumed are charged to this program.
+    pub fn program_invoke_with_units<R: Store>(
+        &self,
+        max_units: u64,
+        call_ctx: &ProgramContext,
+        fn_name: &str,
+        call_args: &[ProgramValue],
+    ) -> Result<R, CallError> {
+        let result = host_program_invoke_with_units(
+            self,
+            call_ctx,
+            fn_name,
+            &CallArgs::encode(call_args),
+            max_units,
+        );
+        unsafe { take_call_result(result) }
+    }
+}
+
+/// Decodes the tagged result of a call from the buffer the host returned, or the error
+/// described by a negative status.
+///
+/// # Safety
+/// [result] must be returned by the host for a call, so a buffer it describes is owned by
+/// this module.
+unsafe fn take_call_result<R: Store>(result: i64) -> Result<R, CallError> {
+    if let Some(err) = CallError::from_status(result) {
+        return Err(err);
     }
+    // The host copies the callee's result into a buffer owned by this module.
+    let bytes = take_packed_buffer(result);
+    Ok(R::from_bytes(&bytes)?)
 }
@@ -1,5 +1,8 @@
-use crate::program::ProgramValue;
-use crate::store::ProgramContext;
+use crate::codec::{Packer, Unpacker};
+use crate::errors::StorageError;
+use crate::program::{int_from_bytes, ProgramValue};
+use crate::store::{to_string, ProgramContext, Store, Tag};
+use std::borrow::Cow;
 
 /// A struct that enforces a fixed length of 32 bytes which represents an address.
 #[derive(Clone, Copy, PartialEq, Eq)]
@@ -10,7 +13,7 @@ pub struct Address {
 impl Address {
     pub const LEN: usize = 32;
     // Constructor function for Address
-    pub fn new(bytes: [u8; Self::LEN]) -> Self {
+    pub const fn new(bytes: [u8; Self::LEN]) -> Self {
         Self { bytes }
     }
     pub fn as_bytes(&self) -> &[u8] {
@@ -18,6 +21,58 @@ impl Address {
     }
 }
 
+/// A 256 bit unsigned integer, held as its big endian bytes.
+#[derive(Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct U256 {
+    // big endian bytes compare in the same order as the values they hold
+    bytes: [u8; Self::LEN],
+}
+
+impl U256 {
+    pub const LEN: usize = 32;
+    pub const ZERO: U256 = U256 {
+        bytes: [0; Self::LEN],
+    };
+    pub const MAX: U256 = U256 {
+        bytes: [u8::MAX; Self::LEN],
+    };
+
+    pub const fn from_be_bytes(bytes: [u8; Self::LEN]) -> Self {
+        Self { bytes }
+    }
+
+    pub const fn to_be_bytes(&self) -> [u8; Self::LEN] {
+        self.bytes
+    }
+}
+
+impl From<u64> for U256 {
+    fn from(value: u64) -> Self {
+        U256::from(u128::from(value))
+    }
+}
+
+impl From<u128> for U256 {
+    fn from(value: u128) -> Self {
+        let mut bytes = [0; Self::LEN];
+        bytes[Self::LEN - 16..].copy_from_slice(&value.to_be_bytes());
+        Self { bytes }
+    }
+}
+
+impl TryFrom<U256> for u128 {
+    type Error = StorageError;
+
+    /// Fails with [StorageError::InvalidBytes] if the value does not fit in a u128.
+    fn try_from(value: U256) -> Result<Self, Self::Error> {
+        let (high, low) = value.bytes.split_at(U256::LEN - 16);
+        if high.iter().any(|byte| *byte != 0) {
+            return Err(StorageError::InvalidBytes());
+        }
+        Ok(u128::from_be_bytes(low.try_into().expect("16 bytes")))
+    }
+}
+
 impl From<String> for ProgramValue {
     fn from(value: String) -> Self {
         ProgramValue::StringObject(value)
@@ -36,38 +91,340 @@ impl From<i64> for ProgramValue {
     }
 }
 
+impl From<u64> for ProgramValue {
+    fn from(value: u64) -> Self {
+        ProgramValue::U64Object(value)
+    }
+}
+
+impl From<u128> for ProgramValue {
+    fn from(value: u128) -> Self {
+        ProgramValue::U128Object(value)
+    }
+}
+
+impl From<i128> for ProgramValue {
+    fn from(value: i128) -> Self {
+        ProgramValue::I128Object(value)
+    }
+}
+
+impl From<U256> for ProgramValue {
+    fn from(value: U256) -> Self {
+        ProgramValue::U256Object(value)
+    }
+}
+
 impl From<Address> for ProgramValue {
     fn from(value: Address) -> Self {
         ProgramValue::AddressObject(value)
     }
 }
 
-impl From<i64> for Address {
-    fn from(value: i64) -> Self {
-        let bytes: [u8; Self::LEN] = unsafe {
-            // We want to copy the bytes here, since [value] represents a ptr created by the host
-            std::slice::from_raw_parts(value as *const u8, Self::LEN)
-                .try_into()
-                .unwrap()
-        };
-        Self { bytes }
+impl TryFrom<ProgramValue> for i64 {
+    type Error = StorageError;
+
+    fn try_from(value: ProgramValue) -> Result<Self, Self::Error> {
+        match value {
+            ProgramValue::IntObject(i) => Ok(i),
+            other => Err(StorageError::TypeMismatch {
+                expected: Tag::INT.as_u8(),
+                found: other.as_tag().as_u8(),
+            }),
+        }
     }
 }
 
-impl From<ProgramValue> for i64 {
-    fn from(value: ProgramValue) -> Self {
+impl TryFrom<ProgramValue> for ProgramContext {
+    type Error = StorageError;
+
+    fn try_from(value: ProgramValue) -> Result<Self, Self::Error> {
         match value {
-            ProgramValue::IntObject(i) => i,
-            _ => panic!("Cannot conver to i64"),
+            ProgramValue::ProgramObject(i) => Ok(i),
+            other => Err(StorageError::TypeMismatch {
+                expected: Tag::PROGRAM.as_u8(),
+                found: other.as_tag().as_u8(),
+            }),
         }
     }
 }
 
-impl From<ProgramValue> for ProgramContext {
-    fn from(value: ProgramValue) -> Self {
-        match value {
-            ProgramValue::ProgramObject(i) => i,
-            _ => panic!("Cannot conver to ProgramContext"),
+// Primitive Store implementations share their tags and byte layout with the
+// matching ProgramValue variant, so either can be used to read a stored value.
+
+impl Store for i64 {
+    fn as_bytes(&self) -> Cow<'_, [u8]> {
+        // hypersdk's codec.Packer uses big endian
+        Cow::Owned(self.to_be_bytes().to_vec())
+    }
+
+    fn as_tag(&self) -> Tag {
+        Tag::INT
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self, StorageError> {
+        int_from_bytes(Tag::INT.strip(bytes)?)
+    }
+}
+
+/// Implements Store for an integer as its big endian bytes, as hypersdk's codec.Packer
+/// packs them.
+macro_rules! impl_store_int {
+    ($($int:ty => $tag:expr),* $(,)?) => {
+        $(
+            impl Store for $int {
+                fn as_bytes(&self) -> Cow<'_, [u8]> {
+                    Cow::Owned(self.to_be_bytes().to_vec())
+                }
+
+                fn as_tag(&self) -> Tag {
+                    $tag
+                }
+
+                fn from_bytes(bytes: &[u8]) -> Result<Self, StorageError> {
+                    let bytes = $tag.strip(bytes)?;
+                    match bytes.try_into() {
+                        Ok(bytes) => Ok(<$int>::from_be_bytes(bytes)),
+                        Err(_) => Err(StorageError::InvalidByteLength(bytes.len())),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_store_int!(u64 => Tag::U64, u128 => Tag::U128, i128 => Tag::I128, U256 => Tag::U256);
+
+impl Store for () {
+    fn as_bytes(&self) -> Cow<'_, [u8]> {
+        Cow::Borrowed(&[])
+    }
+
+    fn as_tag(&self) -> Tag {
+        Tag::UNIT
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self, StorageError> {
+        match Tag::UNIT.strip(bytes)? {
+            [] => Ok(()),
+            bytes => Err(StorageError::InvalidByteLength(bytes.len())),
         }
     }
 }
+
+impl Store for i32 {
+    fn as_bytes(&self) -> Cow<'_, [u8]> {
+        Cow::Owned(self.to_be_bytes().to_vec())
+    }
+
+    fn as_tag(&self) -> Tag {
+        Tag::I32
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self, StorageError> {
+        let bytes = Tag::I32.strip(bytes)?;
+        match bytes.try_into() {
+            Ok(bytes) => Ok(i32::from_be_bytes(bytes)),
+            Err(_) => Err(StorageError::InvalidByteLength(bytes.len())),
+        }
+    }
+}
+
+impl Store for bool {
+    fn as_bytes(&self) -> Cow<'_, [u8]> {
+        Cow::Owned(vec![u8::from(*self)])
+    }
+
+    fn as_tag(&self) -> Tag {
+        Tag::BOOL
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self, StorageError> {
+        match Tag::BOOL.strip(bytes)? {
+            [0] => Ok(false),
+            [1] => Ok(true),
+            [_] => Err(StorageError::InvalidBytes()),
+            bytes => Err(StorageError::InvalidByteLength(bytes.len())),
+        }
+    }
+}
+
+impl Store for String {
+    fn as_bytes(&self) -> Cow<'_, [u8]> {
+        Cow::Borrowed(self.as_bytes())
+    }
+
+    fn as_tag(&self) -> Tag {
+        Tag::STRING
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self, StorageError> {
+        to_string(Tag::STRING.strip(bytes)?.to_vec()).map_err(|_| StorageError::InvalidBytes())
+    }
+}
+
+impl Store for Address {
+    fn as_bytes(&self) -> Cow<'_, [u8]> {
+        Cow::Borrowed(self.as_bytes())
+    }
+
+    fn as_tag(&self) -> Tag {
+        Tag::ADDRESS
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self, StorageError> {
+        match Tag::ADDRESS.strip(bytes)?.try_into() {
+            Ok(bytes) => Ok(Address::new(bytes)),
+            Err(_) => Err(StorageError::InvalidBytes()),
+        }
+    }
+}
+
+impl Store for ProgramContext {
+    fn as_bytes(&self) -> Cow<'_, [u8]> {
+        // Since ProgramContext is a wrapper around a u64
+        Cow::Owned(self.program_id.to_be_bytes().to_vec())
+    }
+
+    fn as_tag(&self) -> Tag {
+        Tag::PROGRAM
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self, StorageError> {
+        int_from_bytes(Tag::PROGRAM.strip(bytes)?).map(ProgramContext::from)
+    }
+}
+
+impl<T: Store> Store for Option<T> {
+    fn as_bytes(&self) -> Cow<'_, [u8]> {
+        match self {
+            None => Cow::Borrowed(&[0]),
+            Some(value) => Cow::Owned([&[1], value.to_tagged_bytes().as_slice()].concat()),
+        }
+    }
+
+    fn as_tag(&self) -> Tag {
+        Tag::OPTION
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self, StorageError> {
+        match Tag::OPTION.strip(bytes)?.split_first() {
+            Some((0, [])) => Ok(None),
+            Some((0, rest)) => Err(StorageError::InvalidByteLength(rest.len())),
+            Some((1, value)) => T::from_bytes(value).map(Some),
+            Some((variant, _)) => Err(StorageError::InvalidVariant(*variant)),
+            None => Err(StorageError::InvalidByteLength(0)),
+        }
+    }
+}
+
+impl<T: Store, E: Store> Store for Result<T, E> {
+    fn as_bytes(&self) -> Cow<'_, [u8]> {
+        let (variant, bytes) = match self {
+            Ok(value) => (0, value.to_tagged_bytes()),
+            Err(err) => (1, err.to_tagged_bytes()),
+        };
+        Cow::Owned([&[variant], bytes.as_slice()].concat())
+    }
+
+    fn as_tag(&self) -> Tag {
+        Tag::RESULT
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self, StorageError> {
+        match Tag::RESULT.strip(bytes)?.split_first() {
+            Some((0, value)) => T::from_bytes(value).map(Ok),
+            Some((1, err)) => E::from_bytes(err).map(Err),
+            Some((variant, _)) => Err(StorageError::InvalidVariant(*variant)),
+            None => Err(StorageError::InvalidByteLength(0)),
+        }
+    }
+}
+
+/// StorageError is stored as the layout of an enum deriving `Storable`, with bytes and
+/// codes widened to `i32` and lengths and limits to `u64`, so exposed functions can return
+/// errors wrapping it.
+impl Store for StorageError {
+    fn as_bytes(&self) -> Cow<'_, [u8]> {
+        let mut packer = Packer::new(0);
+        match self {
+            StorageError::Other(message) => {
+                packer.pack_u8(0);
+                packer.pack(message);
+            }
+            StorageError::InvalidBytes() => packer.pack_u8(1),
+            StorageError::InvalidByteLength(len) => {
+                packer.pack_u8(2);
+                packer.pack(&(*len as u64));
+            }
+            StorageError::InvalidTag(tag) => {
+                packer.pack_u8(3);
+                packer.pack(&i32::from(*tag));
+            }
+            StorageError::TypeMismatch { expected, found } => {
+                packer.pack_u8(4);
+                packer.pack(&i32::from(*expected));
+                packer.pack(&i32::from(*found));
+            }
+            StorageError::InvalidVersion(version) => {
+                packer.pack_u8(5);
+                packer.pack(&i32::from(*version));
+            }
+            StorageError::InvalidVariant(variant) => {
+                packer.pack_u8(6);
+                packer.pack(&i32::from(*variant));
+            }
+            StorageError::HostStoreError(code) => {
+                packer.pack_u8(7);
+                packer.pack(code);
+            }
+            StorageError::HostRetrieveError(code) => {
+                packer.pack_u8(8);
+                packer.pack(code);
+            }
+            StorageError::HostDeleteError(code) => {
+                packer.pack_u8(9);
+                packer.pack(code);
+            }
+            StorageError::InvalidLimit(limit) => {
+                packer.pack_u8(10);
+                packer.pack(&u64::from(*limit));
+            }
+        }
+        Cow::Owned(packer.finish())
+    }
+
+    fn as_tag(&self) -> Tag {
+        Tag::ENUM
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self, StorageError> {
+        let mut unpacker = Unpacker::new(Tag::ENUM.strip(bytes)?, 0)?;
+        let byte = |value: i32| u8::try_from(value).map_err(|_| StorageError::InvalidBytes());
+        let err = match unpacker.unpack_u8()? {
+            0 => StorageError::Other(unpacker.unpack()?),
+            1 => StorageError::InvalidBytes(),
+            2 => StorageError::InvalidByteLength(
+                usize::try_from(unpacker.unpack::<u64>()?)
+                    .map_err(|_| StorageError::InvalidBytes())?,
+            ),
+            3 => StorageError::InvalidTag(byte(unpacker.unpack()?)?),
+            4 => StorageError::TypeMismatch {
+                expected: byte(unpacker.unpack()?)?,
+                found: byte(unpacker.unpack()?)?,
+            },
+            5 => StorageError::InvalidVersion(byte(unpacker.unpack()?)?),
+            6 => StorageError::InvalidVariant(byte(unpacker.unpack()?)?),
+            7 => StorageError::HostStoreError(unpacker.unpack()?),
+            8 => StorageError::HostRetrieveError(unpacker.unpack()?),
+            9 => StorageError::HostDeleteError(unpacker.unpack()?),
+            10 => StorageError::InvalidLimit(
+                u32::try_from(unpacker.unpack::<u64>()?)
+                    .map_err(|_| StorageError::InvalidBytes())?,
+            ),
+            variant => return Err(StorageError::InvalidVariant(variant)),
+        };
+        unpacker.finish()?;
+        Ok(err)
+    }
+}
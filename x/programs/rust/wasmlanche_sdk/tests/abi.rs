@@ -0,0 +1,10 @@
+/// The SDK generates its imports from its own copy of the manifest, which must match the
+/// one the Go runtime embeds. Copy `x/programs/abi/abi.json` over it when this fails.
+#[test]
+fn manifest_matches_runtime() {
+    assert_eq!(
+        include_str!("../abi.json"),
+        include_str!("../../../abi/abi.json"),
+        "wasmlanche_sdk/abi.json is out of date"
+    );
+}
@@ -0,0 +1,49 @@
+use wasmlanche_sdk::errors::StorageError;
+use wasmlanche_sdk::host;
+use wasmlanche_sdk::mock;
+
+#[test]
+fn discards_failed_changes() {
+    let ctx = host::init_program_storage();
+    ctx.store_value("kept", &1_u64).unwrap();
+
+    let updated: Result<(), _> = ctx.atomic(|ctx| {
+        ctx.store_value("kept", &2_u64)?;
+        ctx.store_value("added", &3_u64)?;
+        Err(StorageError::InvalidBytes())
+    });
+    assert!(updated.is_err());
+    assert_eq!(ctx.get_value::<u64>("kept").unwrap(), Some(1));
+    assert_eq!(ctx.get_value::<u64>("added").unwrap(), None);
+}
+
+#[test]
+fn discards_failed_events() {
+    let ctx = host::init_program_storage();
+    host::emit_event(&ctx, "Kept", &[]);
+
+    let emitted: Result<(), StorageError> = ctx.atomic(|ctx| {
+        host::emit_event(ctx, "Dropped", &[]);
+        Err(StorageError::InvalidBytes())
+    });
+    assert!(emitted.is_err());
+    let topics: Vec<_> = mock::events()
+        .into_iter()
+        .map(|event| event.topic)
+        .collect();
+    assert_eq!(topics, ["Kept"]);
+}
+
+#[test]
+#[should_panic(expected = "failed to revert")]
+fn panics_if_savepoint_is_gone() {
+    let ctx = host::init_program_storage();
+    let outer = host::savepoint();
+    ctx.store_value("before", &1_u64).unwrap();
+    let _: Result<(), StorageError> = ctx.atomic(|ctx| {
+        ctx.store_value("value", &1_u64)?;
+        // reverting to an earlier savepoint also discards the one atomic took
+        host::revert(outer);
+        Err(StorageError::InvalidBytes())
+    });
+}
@@ -0,0 +1,80 @@
+use wasmlanche_sdk::collections::StorageMap;
+use wasmlanche_sdk::errors::StorageError;
+use wasmlanche_sdk::host;
+use wasmlanche_sdk::program;
+use wasmlanche_sdk::store::Store;
+
+#[test]
+fn pages_through_map() {
+    let ctx = host::init_program_storage();
+    let map = StorageMap::<u64, u64>::new(ctx, "map");
+    for key in 0..5 {
+        map.insert(&key, &(key * 10)).unwrap();
+    }
+
+    let page = map.page(None, None, 2).unwrap();
+    assert_eq!(page.entries, vec![(0, 0), (1, 10)]);
+    assert_eq!(page.next, Some(2));
+    let page = map.page(page.next.as_ref(), None, 5).unwrap();
+    assert_eq!(page.entries.len(), 3);
+    assert_eq!(page.next, None);
+
+    // a page of no entries could never advance
+    assert!(matches!(
+        map.page(None, None, 0),
+        Err(StorageError::InvalidLimit(0))
+    ));
+    assert_eq!(map.iter(0).count(), 5);
+}
+
+#[test]
+fn iterates_raw_prefix() {
+    let ctx = host::init_program_storage();
+    ctx.store_value("a", &1u64).unwrap();
+    ctx.store_value("b", &2u64).unwrap();
+    let map = StorageMap::<u64, u64>::new(ctx.clone(), "map");
+    map.insert(&1, &3).unwrap();
+
+    let all = ctx.iter_prefix(&[], &[], &[], 10).unwrap();
+    assert_eq!(all.len(), 3);
+    let limited = ctx.iter_prefix(&[], &[], &[], 1).unwrap();
+    assert_eq!(limited, all[..1]);
+    assert_eq!(u64::from_bytes(&limited[0].1).unwrap(), 1);
+}
+
+#[test]
+fn initialized_flag_is_reserved() {
+    let ctx = host::init_program_storage();
+    // fields named like the flag do not mark the program initialized
+    ctx.store_value("initialized", &true).unwrap();
+    ctx.store_value("$initialized", &true).unwrap();
+    assert!(program::mark_initialized(&ctx).unwrap());
+
+    // nor does overwriting or removing them unmark it
+    ctx.store_value("$initialized", &false).unwrap();
+    assert_eq!(ctx.remove_value::<bool>("initialized").unwrap(), Some(true));
+    assert!(!program::mark_initialized(&ctx).unwrap());
+}
+
+#[test]
+fn storage_error_round_trips() {
+    for err in [
+        StorageError::Other("other".to_string()),
+        StorageError::InvalidBytes(),
+        StorageError::InvalidByteLength(7),
+        StorageError::InvalidTag(3),
+        StorageError::TypeMismatch {
+            expected: 7,
+            found: 8,
+        },
+        StorageError::InvalidVersion(1),
+        StorageError::InvalidVariant(2),
+        StorageError::HostStoreError(-1),
+        StorageError::HostRetrieveError(-2),
+        StorageError::HostDeleteError(-3),
+        StorageError::InvalidLimit(0),
+    ] {
+        let decoded = StorageError::from_bytes(&err.to_tagged_bytes()).unwrap();
+        assert_eq!(format!("{decoded:?}"), format!("{err:?}"));
+    }
+}
@@ -0,0 +1,35 @@
+use wasmlanche_sdk::host;
+use wasmlanche_sdk::mock::{self, Entry};
+use wasmlanche_sdk::program::CallArgs;
+use wasmlanche_sdk::store::ProgramContext;
+
+/// Deploys a program whose `emit_as` method emits an event passing the id of [other].
+fn deploy(other: ProgramContext) -> ProgramContext {
+    mock::deploy(Entry::methods(move |method, args_ptr, args_len| {
+        let mut args = unsafe { CallArgs::from_host(args_ptr, args_len) };
+        args.next_arg::<ProgramContext>().unwrap();
+        match method {
+            "emit_as" => host::emit_event(&other, "Spoofed", &[]),
+            _ => return false,
+        };
+        host::set_call_result(&());
+        true
+    }))
+}
+
+#[test]
+fn events_belong_to_the_running_program() {
+    let other = host::init_program_storage();
+    let program = deploy(other.clone());
+
+    mock::call::<()>(&program, "emit_as", &[]).unwrap();
+    let emitters: Vec<_> = mock::events()
+        .into_iter()
+        .map(|event| event.program_id)
+        .collect();
+    assert_eq!(emitters, [program.program_id]);
+
+    // functions called directly emit for the program they pass
+    host::emit_event(&other, "Direct", &[]);
+    assert_eq!(mock::events()[1].program_id, other.program_id);
+}
@@ -0,0 +1,63 @@
+use wasmlanche_sdk::errors::CallError;
+use wasmlanche_sdk::host;
+use wasmlanche_sdk::mock::{self, Entry};
+use wasmlanche_sdk::program::CallArgs;
+use wasmlanche_sdk::store::ProgramContext;
+
+/// The id programs claim to be when invoking another.
+const CLAIMED_ID: i64 = 99;
+
+fn caller_id() -> i64 {
+    host::caller().map_or(0, |caller| caller.program_id as i64)
+}
+
+/// Deploys a program whose `caller` method returns the id of its caller, and whose
+/// `spoof_caller` method invokes it on [callee] while claiming to be [CLAIMED_ID].
+fn deploy(callee: Option<ProgramContext>) -> ProgramContext {
+    mock::deploy(Entry::methods(move |method, args_ptr, args_len| {
+        let mut args = unsafe { CallArgs::from_host(args_ptr, args_len) };
+        let ctx = args.next_arg::<ProgramContext>().unwrap();
+        let result = match method {
+            "caller" => caller_id(),
+            "spoof_caller" => ProgramContext::from(CLAIMED_ID)
+                .program_invoke(callee.as_ref().unwrap_or(&ctx), "caller", &[])
+                .unwrap(),
+            _ => return false,
+        };
+        host::set_call_result(&result);
+        true
+    }))
+}
+
+#[test]
+fn caller_is_the_running_program() {
+    let callee = deploy(None);
+    let caller = deploy(Some(callee.clone()));
+
+    assert_eq!(mock::call::<i64>(&callee, "caller", &[]).unwrap(), 0);
+    assert_eq!(
+        mock::call::<i64>(&caller, "spoof_caller", &[]).unwrap(),
+        caller.program_id as i64
+    );
+    // functions called directly run for the actor, whatever id they pass
+    let called: Result<i64, CallError> =
+        ProgramContext::from(CLAIMED_ID).program_invoke(&callee, "caller", &[]);
+    assert_eq!(called.unwrap(), 0);
+}
+
+#[test]
+fn calls_without_units_fail() {
+    let callee = deploy(None);
+    let caller = ProgramContext::from(0);
+
+    let called: Result<i64, CallError> =
+        caller.program_invoke_with_units(0, &callee, "caller", &[]);
+    assert!(matches!(called, Err(CallError::InsufficientUnits)));
+    let called: Result<i64, CallError> =
+        caller.program_invoke_with_units(1, &callee, "caller", &[]);
+    assert_eq!(called.unwrap(), 0);
+
+    mock::set_remaining_units(0);
+    let called: Result<i64, CallError> = caller.program_invoke(&callee, "caller", &[]);
+    assert!(matches!(called, Err(CallError::InsufficientUnits)));
+}
@@ -0,0 +1,177 @@
+use std::fmt::Debug;
+use wasmlanche_sdk::errors::MathError;
+use wasmlanche_sdk::math::{self, Integer};
+use wasmlanche_sdk::store::Store;
+use wasmlanche_sdk::types::U256;
+
+/// Checks the add, sub and mul edges shared by every Integer, [one] being 1 in T.
+fn check_edges<T: Integer + Debug>(one: T) {
+    let two = math::checked_add(one, one).unwrap();
+    assert_eq!(math::checked_add(T::MAX, T::ZERO), Ok(T::MAX));
+    assert_eq!(math::checked_add(T::MAX, one), Err(MathError::Overflow));
+    assert_eq!(math::checked_sub(T::MIN, T::ZERO), Ok(T::MIN));
+    assert_eq!(math::checked_sub(T::MIN, one), Err(MathError::Underflow));
+    assert_eq!(math::checked_mul(T::MAX, one), Ok(T::MAX));
+    assert_eq!(math::checked_mul(T::MAX, two), Err(MathError::Overflow));
+    assert_eq!(math::checked_mul(T::MAX, T::ZERO), Ok(T::ZERO));
+
+    assert_eq!(math::saturating_add(T::MAX, one), T::MAX);
+    assert_eq!(math::saturating_sub(T::MIN, one), T::MIN);
+    assert_eq!(math::saturating_mul(T::MAX, two), T::MAX);
+}
+
+/// Checks the edges only signed Integers have, [minus_one] being -1 in T.
+fn check_signed_edges<T: Integer + Debug>(minus_one: T) {
+    let one = math::checked_mul(minus_one, minus_one).unwrap();
+    let minus_two = math::checked_add(minus_one, minus_one).unwrap();
+    assert_eq!(
+        math::checked_add(T::MIN, minus_one),
+        Err(MathError::Underflow)
+    );
+    assert_eq!(
+        math::checked_sub(T::MAX, minus_one),
+        Err(MathError::Overflow)
+    );
+    assert_eq!(
+        math::checked_mul(T::MIN, minus_one),
+        Err(MathError::Overflow)
+    );
+    assert_eq!(
+        math::checked_mul(T::MAX, minus_two),
+        Err(MathError::Underflow)
+    );
+    assert_eq!(
+        math::checked_mul(T::MIN, minus_two),
+        Err(MathError::Overflow)
+    );
+    assert_eq!(
+        math::checked_mul_div(T::MIN, minus_one, minus_one),
+        Ok(T::MIN)
+    );
+    assert_eq!(
+        math::checked_mul_div(T::MIN, minus_one, one),
+        Err(MathError::Overflow)
+    );
+
+    assert_eq!(math::saturating_add(T::MIN, minus_one), T::MIN);
+    assert_eq!(math::saturating_mul(T::MIN, minus_one), T::MAX);
+    assert_eq!(math::saturating_mul(T::MAX, minus_two), T::MIN);
+}
+
+fn check_division_by_zero<T: Integer + Debug>(one: T) {
+    assert_eq!(one.checked_mul_div(one, T::ZERO), None);
+    assert_eq!(T::ZERO.checked_mul_div(T::MAX, T::ZERO), None);
+    assert_eq!(T::MAX.checked_mul_div(T::MAX, T::ZERO), None);
+    assert_eq!(
+        math::checked_mul_div(one, one, T::ZERO),
+        Err(MathError::DivisionByZero)
+    );
+    assert_eq!(
+        math::checked_mul_div(T::MAX, T::MAX, T::ZERO),
+        Err(MathError::DivisionByZero)
+    );
+    assert_eq!(
+        math::saturating_mul_div(T::MAX, T::MAX, T::ZERO),
+        Err(MathError::DivisionByZero)
+    );
+}
+
+#[test]
+fn integer_edges() {
+    check_edges(1_i32);
+    check_edges(1_i64);
+    check_edges(1_u64);
+    check_edges(1_i128);
+    check_edges(1_u128);
+    check_edges(U256::from(1_u64));
+
+    check_signed_edges(-1_i32);
+    check_signed_edges(-1_i64);
+    check_signed_edges(-1_i128);
+}
+
+#[test]
+fn division_by_zero() {
+    check_division_by_zero(1_i32);
+    check_division_by_zero(1_i64);
+    check_division_by_zero(1_u64);
+    check_division_by_zero(1_i128);
+    check_division_by_zero(1_u128);
+    check_division_by_zero(U256::from(1_u64));
+}
+
+#[test]
+fn mul_div_keeps_wide_products() {
+    // the products need 256 bits for u128 and 512 bits for U256
+    assert_eq!(
+        math::checked_mul_div(u128::MAX, u128::MAX, u128::MAX),
+        Ok(u128::MAX)
+    );
+    assert_eq!(
+        math::checked_mul_div(U256::MAX, U256::MAX, U256::MAX),
+        Ok(U256::MAX)
+    );
+    let half = U256::from(u128::MAX);
+    let shifted = math::checked_mul(half, U256::from(1_u128 << 64)).unwrap();
+    assert_eq!(
+        math::checked_mul_div(U256::MAX, shifted, shifted),
+        Ok(U256::MAX)
+    );
+    assert_eq!(
+        math::checked_mul_div(U256::MAX, U256::MAX, shifted),
+        Err(MathError::Overflow)
+    );
+    assert_eq!(
+        math::saturating_mul_div(U256::MAX, U256::MAX, U256::from(2_u64)),
+        Ok(U256::MAX)
+    );
+    assert_eq!(
+        math::checked_mul_div(i128::MAX, i128::MAX, i128::MAX),
+        Ok(i128::MAX)
+    );
+    assert_eq!(
+        math::checked_mul_div(i128::MIN, i128::MAX, i128::MAX),
+        Ok(i128::MIN)
+    );
+}
+
+#[test]
+fn mul_div_signs() {
+    // results are rounded towards zero
+    assert_eq!(math::checked_mul_div(7_i64, 3, 2), Ok(10));
+    assert_eq!(math::checked_mul_div(-7_i64, 3, 2), Ok(-10));
+    assert_eq!(math::checked_mul_div(7_i64, -3, 2), Ok(-10));
+    assert_eq!(math::checked_mul_div(7_i64, 3, -2), Ok(-10));
+    assert_eq!(math::checked_mul_div(-7_i64, -3, -2), Ok(-10));
+    assert_eq!(math::checked_mul_div(-7_i64, -3, 2), Ok(10));
+
+    assert_eq!(math::checked_mul_div(7_i128, 3, 2), Ok(10));
+    assert_eq!(math::checked_mul_div(-7_i128, 3, 2), Ok(-10));
+    assert_eq!(math::checked_mul_div(7_i128, -3, 2), Ok(-10));
+    assert_eq!(math::checked_mul_div(7_i128, 3, -2), Ok(-10));
+    assert_eq!(math::checked_mul_div(-7_i128, -3, -2), Ok(-10));
+    assert_eq!(math::checked_mul_div(-7_i128, -3, 2), Ok(10));
+
+    assert_eq!(
+        math::checked_mul_div(i64::MAX, -2, 1),
+        Err(MathError::Underflow)
+    );
+    assert_eq!(
+        math::checked_mul_div(i128::MAX, -2, 1),
+        Err(MathError::Underflow)
+    );
+    assert_eq!(math::saturating_mul_div(i128::MIN, -1, 1), Ok(i128::MAX));
+    assert_eq!(math::saturating_mul_div(i64::MIN, 2, 1), Ok(i64::MIN));
+}
+
+#[test]
+fn math_error_round_trips() {
+    for err in [
+        MathError::Overflow,
+        MathError::Underflow,
+        MathError::DivisionByZero,
+    ] {
+        assert_eq!(MathError::from_bytes(&err.to_tagged_bytes()).unwrap(), err);
+    }
+    assert!(MathError::from_bytes(&MathError::Overflow.as_bytes()).is_err());
+}
@@ -0,0 +1,921 @@
+//! Runs programs compiled to WASM in an embedded engine, against an in-memory host
+//! implementing the host modules of `x/programs/abi/abi.json` the way the SDK expects,
+//! so integration tests can call built programs without the Go runtime.
+//!
+//! Every call runs in a new instance of the program's code, as it does in the runtime,
+//! so only storage persists between calls, and a call that fails discards its changes to
+//! it. Storage is shared by every program of a [TestHost], and calls between programs run
+//! in the same host. The host collects the events programs emit, dropping those of failed
+//! calls.
+
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use std::rc::Rc;
+
+use thiserror::Error;
+use wasmi::core::TrapCode;
+use wasmi::{Caller, Config, Engine, Extern, Linker, Module, Store as WasmStore, Value};
+use wasmlanche_sdk::env::Env;
+use wasmlanche_sdk::errors::CallError;
+use wasmlanche_sdk::event::EmittedEvent;
+use wasmlanche_sdk::host::{
+    ABI_VERSION, INVOKE_INSUFFICIENT_UNITS, INVOKE_TRAP, INVOKE_UNKNOWN_METHOD, MAP_NOT_FOUND,
+    MAP_UNKNOWN_PROGRAM,
+};
+use wasmlanche_sdk::program::{method_id, CallArgs, Initialized, ProgramValue};
+use wasmlanche_sdk::store::{ProgramContext, Store};
+use wasmlanche_sdk::types::Address;
+
+const MEMORY: &str = "memory";
+const ALLOC_FN: &str = "alloc";
+/// Exported by the SDK, returning the version of the host ABI the program was built against.
+const ABI_VERSION_FN: &str = "abi_version";
+/// Exported by programs built with the SDK's program macro.
+const DISPATCH_FN: &str = "dispatch";
+/// Appended to the name of a function exposed with the SDK's expose macro.
+const GUEST_FN_SUFFIX: &str = "_guest";
+/// Returned by the host for failures the runtime reports with its generic error code.
+const HOST_ERR: i32 = -1;
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("failed to read program: {0}")]
+    Read(#[from] std::io::Error),
+
+    #[error("failed to load program: {0}")]
+    Load(wasmi::Error),
+
+    #[error("program trapped: {0}")]
+    Trap(wasmi::Error),
+
+    #[error("{0}")]
+    Call(#[from] CallError),
+
+    #[error("the call did not create a program")]
+    NoProgramCreated,
+
+    #[error(
+        "program was built against abi version {found:?}, the host uses {}",
+        ABI_VERSION
+    )]
+    AbiVersion { found: Option<u32> },
+}
+
+/// The value a key held before it was stored or deleted, restored by reverting.
+struct Change {
+    program_id: u64,
+    key: Vec<u8>,
+    value: Option<Vec<u8>>,
+}
+
+/// The state shared by every program of a host.
+struct State {
+    storage: HashMap<u64, BTreeMap<Vec<u8>, Vec<u8>>>,
+    // every change to storage, so the position in it is a savepoint
+    journal: Vec<Change>,
+    events: Vec<EmittedEvent>,
+    code: HashMap<u64, Rc<Module>>,
+    last_program_id: u64,
+    actor: Option<Address>,
+    env: Env,
+    units: u64,
+}
+
+impl Default for State {
+    fn default() -> Self {
+        Self {
+            storage: HashMap::new(),
+            journal: Vec::new(),
+            events: Vec::new(),
+            code: HashMap::new(),
+            last_program_id: 0,
+            actor: None,
+            env: Env::default(),
+            units: u64::MAX,
+        }
+    }
+}
+
+impl State {
+    /// Returns a savepoint of storage and the events emitted, packing the number of events
+    /// in the upper 32 bits and the length of the journal in the lower 32 bits.
+    fn savepoint(&self) -> i64 {
+        (self.events.len() as i64) << 32 | self.journal.len() as i64
+    }
+
+    /// Undoes every change to storage made and drops every event emitted after [savepoint].
+    /// Returns false if [savepoint] is not one of the savepoints still held.
+    fn revert(&mut self, savepoint: i64) -> bool {
+        let Ok(savepoint) = u64::try_from(savepoint) else {
+            return false;
+        };
+        let (emitted, changed) = (
+            (savepoint >> 32) as usize,
+            (savepoint & 0xffff_ffff) as usize,
+        );
+        if emitted > self.events.len() || changed > self.journal.len() {
+            return false;
+        }
+        for change in self.journal.drain(changed..).rev() {
+            let storage = self.storage.entry(change.program_id).or_default();
+            match change.value {
+                Some(value) => storage.insert(change.key, value),
+                None => storage.remove(&change.key),
+            };
+        }
+        self.events.truncate(emitted);
+        true
+    }
+}
+
+/// A call executing in the host, held by the store of the instance running it.
+struct Frame {
+    host: TestHost,
+    program_id: u64,
+    caller: u64,
+    // the units the call may consume, as the store only counts those consumed
+    units: u64,
+    result: Option<Vec<u8>>,
+    error: i64,
+}
+
+/// Why running a call failed.
+enum Failure {
+    Load(wasmi::Error),
+    AbiVersion(Option<u32>),
+    Trap(wasmi::Error),
+    Status(i64),
+}
+
+impl Failure {
+    /// Returns the failure of a call that trapped with [err], which is reported as running
+    /// out of units if it ran out of fuel.
+    fn trapped(err: wasmi::Error) -> Self {
+        match &err {
+            wasmi::Error::Trap(trap) if matches!(trap.trap_code(), Some(TrapCode::OutOfFuel)) => {
+                Failure::Status(INVOKE_INSUFFICIENT_UNITS)
+            }
+            _ => Failure::Trap(err),
+        }
+    }
+}
+
+impl From<Failure> for Error {
+    fn from(failure: Failure) -> Self {
+        match failure {
+            Failure::Load(err) => Error::Load(err),
+            Failure::AbiVersion(found) => Error::AbiVersion { found },
+            Failure::Trap(err) => Error::Trap(err),
+            Failure::Status(status) => {
+                Error::Call(CallError::from_status(status).unwrap_or(CallError::Host(status)))
+            }
+        }
+    }
+}
+
+/// TestHost deploys programs and holds their storage, the actor and the environment
+/// they run in. Clones share the same state.
+#[derive(Clone)]
+pub struct TestHost {
+    engine: Engine,
+    // defining host functions registers their types with the engine, which is locked
+    // while a module runs, so calls between programs share the linker
+    linker: Rc<Linker<Frame>>,
+    state: Rc<RefCell<State>>,
+}
+
+impl Default for TestHost {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TestHost {
+    pub fn new() -> Self {
+        let mut config = Config::default();
+        config.consume_fuel(true);
+        let engine = Engine::new(&config);
+        Self {
+            linker: Rc::new(linker(&engine)),
+            engine,
+            state: Rc::default(),
+        }
+    }
+
+    /// Sets the address of the transaction signer seen by every program.
+    pub fn with_actor(self, actor: Address) -> Self {
+        self.set_actor(actor);
+        self
+    }
+
+    /// Sets the block and transaction every program runs in.
+    pub fn with_env(self, env: Env) -> Self {
+        self.set_env(env);
+        self
+    }
+
+    /// Sets the units each call from the actor may consume, which is unlimited by default.
+    /// Every instruction consumes a unit.
+    pub fn with_units(self, units: u64) -> Self {
+        self.set_units(units);
+        self
+    }
+
+    pub fn set_units(&self, units: u64) {
+        self.state.borrow_mut().units = units;
+    }
+
+    pub fn set_actor(&self, actor: Address) {
+        self.state.borrow_mut().actor = Some(actor);
+    }
+
+    pub fn set_env(&self, env: Env) {
+        self.state.borrow_mut().env = env;
+    }
+
+    /// Returns every event emitted by the programs of this host, in the order they were
+    /// emitted.
+    pub fn events(&self) -> Vec<EmittedEvent> {
+        self.state.borrow().events.clone()
+    }
+
+    /// Creates a program running the compiled [wasm], with empty storage.
+    pub fn deploy(&self, wasm: impl AsRef<[u8]>) -> Result<Program, Error> {
+        let module = Module::new(&self.engine, wasm.as_ref()).map_err(Error::Load)?;
+        let mut state = self.state.borrow_mut();
+        let id = create_program(&mut state);
+        state.code.insert(id, Rc::new(module));
+        Ok(self.program(id))
+    }
+
+    /// Creates a program running the compiled module at [path], such as the `.wasm`
+    /// output of a program crate.
+    pub fn deploy_file(&self, path: impl AsRef<Path>) -> Result<Program, Error> {
+        self.deploy(std::fs::read(path)?)
+    }
+
+    /// Returns the program with [id], such as the id returned by a call creating one.
+    pub fn program(&self, id: u64) -> Program {
+        Program {
+            id,
+            host: self.clone(),
+        }
+    }
+
+    /// Runs [method] of [program_id] in a new instance of its code, which may consume up
+    /// to [units]. Returns the units it consumed, and the tagged result it set. A call that
+    /// fails is reverted as `InvokeModule.invoke` in x/programs/runtime/program_invoke.go does.
+    fn run(
+        &self,
+        program_id: u64,
+        caller: u64,
+        method: &str,
+        args: &[u8],
+        units: u64,
+    ) -> (u64, Result<Vec<u8>, Failure>) {
+        let Some(module) = self.state.borrow().code.get(&program_id).cloned() else {
+            return (0, Err(Failure::Status(i64::from(HOST_ERR))));
+        };
+        let mut store = WasmStore::new(
+            &self.engine,
+            Frame {
+                host: self.clone(),
+                program_id,
+                caller,
+                units,
+                result: None,
+                error: 0,
+            },
+        );
+        store.add_fuel(units).expect("fuel metering is enabled");
+        let savepoint = self.state.borrow().savepoint();
+
+        let status = self.execute(&mut store, &module, method, args);
+        let consumed = store.fuel_consumed().unwrap_or_default();
+        let frame = store.into_data();
+        let result = match status {
+            Err(failure) => Err(failure),
+            Ok(_) if frame.error != 0 => Err(Failure::Status(frame.error)),
+            Ok(status) if status < 0 => Err(Failure::Status(status)),
+            Ok(_) => frame.result.ok_or(Failure::Status(i64::from(HOST_ERR))),
+        };
+        if result.is_err() {
+            self.state.borrow_mut().revert(savepoint);
+        }
+        (consumed, result)
+    }
+
+    /// Instantiates [module] in [store] and calls [method] with [args], returning the
+    /// status returned by a dispatch function.
+    fn execute(
+        &self,
+        store: &mut WasmStore<Frame>,
+        module: &Module,
+        method: &str,
+        args: &[u8],
+    ) -> Result<i64, Failure> {
+        let instance = self
+            .linker
+            .instantiate(&mut *store, module)
+            .and_then(|instance| instance.start(&mut *store))
+            .map_err(Failure::Load)?;
+
+        // programs built against another host abi are rejected before they run, as the
+        // runtime does when it loads them
+        let version = instance
+            .get_typed_func::<(), u32>(&*store, ABI_VERSION_FN)
+            .ok()
+            .map(|abi_version| {
+                abi_version
+                    .call(&mut *store, ())
+                    .map_err(wasmi::Error::from)
+            })
+            .transpose()
+            .map_err(Failure::trapped)?;
+        if version != Some(ABI_VERSION) {
+            return Err(Failure::AbiVersion(version));
+        }
+
+        // programs built with the program macro check the method in their dispatch function
+        let dispatch = instance.get_func(&*store, DISPATCH_FN);
+        let exposed = instance.get_func(&*store, &format!("{method}{GUEST_FN_SUFFIX}"));
+        if dispatch.is_none() && exposed.is_none() {
+            return Err(Failure::Status(INVOKE_UNKNOWN_METHOD));
+        }
+
+        let memory = instance
+            .get_memory(&*store, MEMORY)
+            .ok_or(Failure::Status(i64::from(HOST_ERR)))?;
+        let args_ptr = instance
+            .get_typed_func::<u32, u32>(&*store, ALLOC_FN)
+            .and_then(|alloc| Ok(alloc.call(&mut *store, args.len() as u32)?))
+            .map_err(Failure::trapped)?;
+        memory
+            .write(&mut *store, args_ptr as usize, args)
+            .map_err(|_| Failure::Status(i64::from(HOST_ERR)))?;
+        let args_len = Value::I32(args.len() as i32);
+
+        match (dispatch, exposed) {
+            (Some(dispatch), _) => {
+                let mut status = [Value::I64(0)];
+                let params = [
+                    Value::I32(method_id(method) as i32),
+                    Value::I32(args_ptr as i32),
+                    args_len,
+                ];
+                dispatch
+                    .call(&mut *store, &params, &mut status)
+                    .map_err(Failure::trapped)?;
+                Ok(status[0].i64().unwrap_or_default())
+            }
+            (None, Some(exposed)) => {
+                // exposed functions may also return their result directly, which is ignored
+                let mut results: Vec<_> = exposed
+                    .ty(&*store)
+                    .results()
+                    .iter()
+                    .map(|ty| Value::default(*ty))
+                    .collect();
+                exposed
+                    .call(
+                        &mut *store,
+                        &[Value::I32(args_ptr as i32), args_len],
+                        &mut results,
+                    )
+                    .map_err(Failure::trapped)?;
+                Ok(0)
+            }
+            (None, None) => unreachable!("checked above"),
+        }
+    }
+}
+
+/// A program deployed to a [TestHost].
+#[derive(Clone)]
+pub struct Program {
+    id: u64,
+    host: TestHost,
+}
+
+impl Program {
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// Returns the context passed to other programs to call this one.
+    pub fn context(&self) -> ProgramContext {
+        ProgramContext::from(self.id as i64)
+    }
+
+    /// Calls [method] as the actor, passing this program's context ahead of [args] as
+    /// calls from other programs do, and decodes the tagged value it returned as [R].
+    pub fn call<R: Store>(&self, method: &str, args: &[ProgramValue]) -> Result<R, Error> {
+        let result = self.run(method, args)?;
+        Ok(R::from_bytes(&result).map_err(CallError::from)?)
+    }
+
+    /// Calls the `#[init]` method [method] of this program's code like [Program::call], and
+    /// returns the program it created along with the value it returned as [R].
+    pub fn init<R: Store>(
+        &self,
+        method: &str,
+        args: &[ProgramValue],
+    ) -> Result<(Program, R), Error> {
+        let result = self.run(method, args)?;
+        let initialized =
+            Initialized::<R>::from_bytes(&result).map_err(|_| Error::NoProgramCreated)?;
+        Ok((
+            self.host.program(initialized.program.program_id),
+            initialized.result,
+        ))
+    }
+
+    /// Runs [method] as the actor with this program's context ahead of [args], and returns
+    /// the tagged result it set.
+    fn run(&self, method: &str, args: &[ProgramValue]) -> Result<Vec<u8>, Error> {
+        let mut encoded = CallArgs::encode(&[ProgramValue::ProgramObject(self.context())]);
+        encoded.extend_from_slice(&CallArgs::encode(args));
+        // 0 is never the id of a program, so the callee sees no caller
+        let units = self.host.state.borrow().units;
+        Ok(self.host.run(self.id, 0, method, &encoded, units).1?)
+    }
+
+    /// Returns a copy of every key and tagged value the program stores, so tests can
+    /// compare its storage before and after a call.
+    pub fn storage_snapshot(&self) -> BTreeMap<Vec<u8>, Vec<u8>> {
+        self.host
+            .state
+            .borrow()
+            .storage
+            .get(&self.id)
+            .cloned()
+            .unwrap_or_default()
+    }
+}
+
+fn create_program(state: &mut State) -> u64 {
+    state.last_program_id += 1;
+    let id = state.last_program_id;
+    state.storage.insert(id, BTreeMap::new());
+    id
+}
+
+/// Reads [len] bytes at [ptr] from the memory of the calling module.
+fn read(caller: &Caller<'_, Frame>, ptr: u32, len: u32) -> Option<Vec<u8>> {
+    let memory = caller.get_export(MEMORY).and_then(Extern::into_memory)?;
+    let mut buf = vec![0; len as usize];
+    memory.read(caller, ptr as usize, &mut buf).ok()?;
+    Some(buf)
+}
+
+/// Writes [bytes] at [ptr] in the memory of the calling module.
+fn write(caller: &mut Caller<'_, Frame>, ptr: u32, bytes: &[u8]) -> Option<()> {
+    let memory = caller.get_export(MEMORY).and_then(Extern::into_memory)?;
+    memory.write(caller, ptr as usize, bytes).ok()
+}
+
+/// Writes [bytes] to a buffer allocated in the calling module, which takes ownership of
+/// it, and returns its offset.
+fn write_new(caller: &mut Caller<'_, Frame>, bytes: &[u8]) -> Option<u32> {
+    let alloc = caller.get_export(ALLOC_FN).and_then(Extern::into_func)?;
+    let ptr = alloc
+        .typed::<u32, u32>(&*caller)
+        .and_then(|alloc| Ok(alloc.call(&mut *caller, bytes.len() as u32)?))
+        .ok()?;
+    write(caller, ptr, bytes)?;
+    Some(ptr)
+}
+
+/// Returns the offset of a buffer in the upper 32 bits and its length in the lower 32 bits.
+fn pack(ptr: u32, len: usize) -> i64 {
+    i64::from(ptr) << 32 | len as i64
+}
+
+/// Returns a linker defining every host function of the ABI manifest.
+fn linker(engine: &Engine) -> Linker<Frame> {
+    let mut linker = Linker::new(engine);
+    define_map(&mut linker);
+    define_savepoints(&mut linker);
+    define_program(&mut linker);
+    define_context(&mut linker);
+    define_env(&mut linker);
+    define_event(&mut linker);
+    define_meter(&mut linker);
+    linker
+}
+
+fn define_map(linker: &mut Linker<Frame>) {
+    linker
+        .func_wrap("map", "init_program", |caller: Caller<'_, Frame>| -> i64 {
+            let frame = caller.data();
+            let mut state = frame.host.state.borrow_mut();
+            let id = create_program(&mut state);
+            // storage created by a running program runs the same code
+            if let Some(code) = state.code.get(&frame.program_id).cloned() {
+                state.code.insert(id, code);
+            }
+            id as i64
+        })
+        .expect("defined once");
+    linker
+        .func_wrap(
+            "map",
+            "store_bytes",
+            |caller: Caller<'_, Frame>,
+             id: u64,
+             key_ptr: u32,
+             key_len: u32,
+             value_ptr: u32,
+             value_len: u32|
+             -> i32 {
+                let (Some(key), Some(value)) = (
+                    read(&caller, key_ptr, key_len),
+                    read(&caller, value_ptr, value_len),
+                ) else {
+                    return HOST_ERR;
+                };
+                let mut state = caller.data().host.state.borrow_mut();
+                let state = &mut *state;
+                match state.storage.get_mut(&id) {
+                    Some(storage) => {
+                        let value = storage.insert(key.clone(), value);
+                        state.journal.push(Change {
+                            program_id: id,
+                            key,
+                            value,
+                        });
+                        0
+                    }
+                    None => MAP_UNKNOWN_PROGRAM,
+                }
+            },
+        )
+        .expect("defined once");
+    linker
+        .func_wrap(
+            "map",
+            "get_bytes_len",
+            |caller: Caller<'_, Frame>, id: u64, key_ptr: u32, key_len: u32| -> i32 {
+                let Some(key) = read(&caller, key_ptr, key_len) else {
+                    return HOST_ERR;
+                };
+                match caller.data().host.state.borrow().storage.get(&id) {
+                    Some(storage) => storage
+                        .get(&key)
+                        .map_or(MAP_NOT_FOUND, |value| value.len() as i32),
+                    None => MAP_UNKNOWN_PROGRAM,
+                }
+            },
+        )
+        .expect("defined once");
+    linker
+        .func_wrap(
+            "map",
+            "get_bytes",
+            |mut caller: Caller<'_, Frame>,
+             id: u64,
+             key_ptr: u32,
+             key_len: u32,
+             val_len: i32|
+             -> i32 {
+                if val_len < 0 {
+                    return HOST_ERR;
+                }
+                let Some(key) = read(&caller, key_ptr, key_len) else {
+                    return HOST_ERR;
+                };
+                let value = match caller.data().host.state.borrow().storage.get(&id) {
+                    Some(storage) => storage.get(&key).cloned().ok_or(MAP_NOT_FOUND),
+                    None => Err(MAP_UNKNOWN_PROGRAM),
+                };
+                match value {
+                    Ok(value) => write_new(&mut caller, &value).map_or(HOST_ERR, |ptr| ptr as i32),
+                    Err(code) => code,
+                }
+            },
+        )
+        .expect("defined once");
+    linker
+        .func_wrap(
+            "map",
+            "delete_bytes",
+            |caller: Caller<'_, Frame>, id: u64, key_ptr: u32, key_len: u32| -> i32 {
+                let Some(key) = read(&caller, key_ptr, key_len) else {
+                    return HOST_ERR;
+                };
+                let mut state = caller.data().host.state.borrow_mut();
+                let state = &mut *state;
+                let Some(storage) = state.storage.get_mut(&id) else {
+                    return MAP_UNKNOWN_PROGRAM;
+                };
+                match storage.remove(&key) {
+                    Some(value) => {
+                        state.journal.push(Change {
+                            program_id: id,
+                            key,
+                            value: Some(value),
+                        });
+                        0
+                    }
+                    None => MAP_NOT_FOUND,
+                }
+            },
+        )
+        .expect("defined once");
+    linker
+        .func_wrap(
+            "map",
+            "iter_prefix",
+            |mut caller: Caller<'_, Frame>,
+             id: u64,
+             prefix_ptr: u32,
+             prefix_len: u32,
+             start_ptr: u32,
+             start_len: u32,
+             end_ptr: u32,
+             end_len: u32,
+             limit: u32|
+             -> i64 {
+                let (Some(prefix), Some(start), Some(end)) = (
+                    read(&caller, prefix_ptr, prefix_len),
+                    read(&caller, start_ptr, start_len),
+                    read(&caller, end_ptr, end_len),
+                ) else {
+                    return i64::from(HOST_ERR);
+                };
+                let entries = {
+                    let state = caller.data().host.state.borrow();
+                    let Some(storage) = state.storage.get(&id) else {
+                        return i64::from(MAP_UNKNOWN_PROGRAM);
+                    };
+                    // each entry is a big endian u32 key length, the key, a big endian
+                    // u32 value length and the value
+                    let mut entries = Vec::new();
+                    for (key, value) in storage
+                        .range(prefix.clone()..)
+                        .take_while(|(key, _)| key.starts_with(&prefix))
+                        .filter(|(key, _)| start.is_empty() || **key >= start)
+                        .filter(|(key, _)| end.is_empty() || **key < end)
+                        .take(limit as usize)
+                    {
+                        entries.extend_from_slice(&(key.len() as u32).to_be_bytes());
+                        entries.extend_from_slice(key);
+                        entries.extend_from_slice(&(value.len() as u32).to_be_bytes());
+                        entries.extend_from_slice(value);
+                    }
+                    entries
+                };
+                if entries.is_empty() {
+                    return 0;
+                }
+                write_new(&mut caller, &entries)
+                    .map_or(i64::from(HOST_ERR), |ptr| pack(ptr, entries.len()))
+            },
+        )
+        .expect("defined once");
+}
+
+/// Returns the units the running call may still consume.
+fn remaining_units(caller: &Caller<'_, Frame>) -> u64 {
+    let consumed = caller.fuel_consumed().unwrap_or_default();
+    caller.data().units.saturating_sub(consumed)
+}
+
+/// Invokes [method] of [call_program_id] with the arguments read from the running program,
+/// which may consume up to [max_units] of the units the caller has left. Follows the protocol
+/// of `InvokeModule.invoke` in x/programs/runtime/program_invoke.go.
+fn invoke(
+    caller: &mut Caller<'_, Frame>,
+    call_program_id: u64,
+    method_ptr: u32,
+    method_len: u32,
+    args_ptr: u32,
+    args_len: u32,
+    max_units: u64,
+) -> i64 {
+    let Some(method) =
+        read(caller, method_ptr, method_len).and_then(|method| String::from_utf8(method).ok())
+    else {
+        return i64::from(HOST_ERR);
+    };
+    let Some(call_args) = read(caller, args_ptr, args_len) else {
+        return i64::from(HOST_ERR);
+    };
+    let callee = ProgramContext::from(call_program_id as i64);
+    let mut args = CallArgs::encode(&[ProgramValue::ProgramObject(callee)]);
+    args.extend_from_slice(&call_args);
+
+    // the callee is called by the running program, whatever id it passed
+    let frame = caller.data();
+    let (caller_id, host) = (frame.program_id, frame.host.clone());
+    let units = max_units.min(remaining_units(caller));
+    let (consumed, result) = host.run(call_program_id, caller_id, &method, &args, units);
+    // the callee's units are charged to the caller
+    if caller.consume_fuel(consumed).is_err() {
+        return INVOKE_INSUFFICIENT_UNITS;
+    }
+    match result {
+        Ok(result) => {
+            write_new(caller, &result).map_or(i64::from(HOST_ERR), |ptr| pack(ptr, result.len()))
+        }
+        Err(Failure::Load(_) | Failure::AbiVersion(_)) => i64::from(HOST_ERR),
+        Err(Failure::Trap(_)) => INVOKE_TRAP,
+        Err(Failure::Status(status)) => status,
+    }
+}
+
+fn define_savepoints(linker: &mut Linker<Frame>) {
+    linker
+        .func_wrap("map", "savepoint", |caller: Caller<'_, Frame>| -> i64 {
+            caller.data().host.state.borrow().savepoint()
+        })
+        .expect("defined once");
+    linker
+        .func_wrap(
+            "map",
+            "revert",
+            |caller: Caller<'_, Frame>, savepoint: i64| -> i32 {
+                match caller.data().host.state.borrow_mut().revert(savepoint) {
+                    true => 0,
+                    false => HOST_ERR,
+                }
+            },
+        )
+        .expect("defined once");
+}
+
+fn define_program(linker: &mut Linker<Frame>) {
+    linker
+        .func_wrap(
+            "program",
+            "invoke_program",
+            |mut caller: Caller<'_, Frame>,
+             _program_id: u64,
+             call_program_id: u64,
+             method_ptr: u32,
+             method_len: u32,
+             args_ptr: u32,
+             args_len: u32|
+             -> i64 {
+                invoke(
+                    &mut caller,
+                    call_program_id,
+                    method_ptr,
+                    method_len,
+                    args_ptr,
+                    args_len,
+                    u64::MAX,
+                )
+            },
+        )
+        .expect("defined once");
+    linker
+        .func_wrap(
+            "program",
+            "invoke_program_with_units",
+            |mut caller: Caller<'_, Frame>,
+             _program_id: u64,
+             call_program_id: u64,
+             method_ptr: u32,
+             method_len: u32,
+             args_ptr: u32,
+             args_len: u32,
+             max_units: u64|
+             -> i64 {
+                invoke(
+                    &mut caller,
+                    call_program_id,
+                    method_ptr,
+                    method_len,
+                    args_ptr,
+                    args_len,
+                    max_units,
+                )
+            },
+        )
+        .expect("defined once");
+    linker
+        .func_wrap(
+            "program",
+            "set_call_result",
+            |mut caller: Caller<'_, Frame>, result_ptr: u32, result_len: u32| -> i32 {
+                let Some(result) = read(&caller, result_ptr, result_len) else {
+                    return HOST_ERR;
+                };
+                caller.data_mut().result = Some(result);
+                0
+            },
+        )
+        .expect("defined once");
+    linker
+        .func_wrap(
+            "program",
+            "set_call_error",
+            |mut caller: Caller<'_, Frame>, code: i64| -> i32 {
+                // only failure codes can be set
+                if code >= 0 {
+                    return HOST_ERR;
+                }
+                caller.data_mut().error = code;
+                0
+            },
+        )
+        .expect("defined once");
+}
+
+fn define_context(linker: &mut Linker<Frame>) {
+    linker
+        .func_wrap(
+            "context",
+            "actor",
+            |mut caller: Caller<'_, Frame>, actor_ptr: u32| -> i32 {
+                let Some(actor) = caller.data().host.state.borrow().actor else {
+                    return HOST_ERR;
+                };
+                write(&mut caller, actor_ptr, actor.as_bytes()).map_or(HOST_ERR, |_| 0)
+            },
+        )
+        .expect("defined once");
+    linker
+        .func_wrap("context", "caller", |caller: Caller<'_, Frame>| -> i64 {
+            caller.data().caller as i64
+        })
+        .expect("defined once");
+}
+
+fn define_env(linker: &mut Linker<Frame>) {
+    linker
+        .func_wrap("env", "height", |caller: Caller<'_, Frame>| -> i64 {
+            caller.data().host.state.borrow().env.height as i64
+        })
+        .expect("defined once");
+    linker
+        .func_wrap("env", "timestamp", |caller: Caller<'_, Frame>| -> i64 {
+            caller.data().host.state.borrow().env.timestamp as i64
+        })
+        .expect("defined once");
+    linker
+        .func_wrap(
+            "env",
+            "chain_id",
+            |mut caller: Caller<'_, Frame>, id_ptr: u32| -> i32 {
+                let id = caller.data().host.state.borrow().env.chain_id;
+                write(&mut caller, id_ptr, id.as_bytes()).map_or(HOST_ERR, |_| 0)
+            },
+        )
+        .expect("defined once");
+    linker
+        .func_wrap(
+            "env",
+            "tx_id",
+            |mut caller: Caller<'_, Frame>, id_ptr: u32| -> i32 {
+                let id = caller.data().host.state.borrow().env.tx_id;
+                write(&mut caller, id_ptr, id.as_bytes()).map_or(HOST_ERR, |_| 0)
+            },
+        )
+        .expect("defined once");
+}
+
+fn define_event(linker: &mut Linker<Frame>) {
+    linker
+        .func_wrap(
+            "event",
+            "emit_event",
+            |caller: Caller<'_, Frame>,
+             _id: u64,
+             topic_ptr: u32,
+             topic_len: u32,
+             payload_ptr: u32,
+             payload_len: u32|
+             -> i32 {
+                let Some(topic) = read(&caller, topic_ptr, topic_len)
+                    .and_then(|topic| String::from_utf8(topic).ok())
+                else {
+                    return HOST_ERR;
+                };
+                let Some(payload) = read(&caller, payload_ptr, payload_len) else {
+                    return HOST_ERR;
+                };
+                // events belong to the running program, whatever id it passes
+                let frame = caller.data();
+                frame.host.state.borrow_mut().events.push(EmittedEvent {
+                    program_id: frame.program_id,
+                    topic,
+                    payload,
+                });
+                0
+            },
+        )
+        .expect("defined once");
+}
+
+fn define_meter(linker: &mut Linker<Frame>) {
+    linker
+        .func_wrap(
+            "meter",
+            "remaining_units",
+            |caller: Caller<'_, Frame>| -> i64 { remaining_units(&caller) as i64 },
+        )
+        .expect("defined once");
+}
@@ -0,0 +1,84 @@
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use storable_macro::Storable;
+use wasmlanche_sdk::errors::{CallError, MathError, StorageError};
+use wasmlanche_sdk::types::Address;
+use wasmlanche_test::{Error, TestHost};
+
+const WASM_TARGET: &str = "wasm32-unknown-unknown";
+
+/// The error returned by the counter example's `inc`, with the layout of its `CounterError`.
+#[derive(Storable, Debug)]
+enum CounterError {
+    Math(MathError),
+    #[allow(dead_code)]
+    Storage(StorageError),
+}
+
+/// Builds the example program [name] for WASM and returns the path of its module, or
+/// None if the WASM target is not installed.
+fn build_example(name: &str) -> Option<PathBuf> {
+    let libdir = Command::new("rustc")
+        .args(["--print", "target-libdir", "--target", WASM_TARGET])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())?;
+    let libdir = PathBuf::from(String::from_utf8(libdir.stdout).ok()?.trim());
+    if !libdir.is_dir() {
+        eprintln!("skipping, the {WASM_TARGET} target is not installed");
+        return None;
+    }
+
+    // a separate target directory, as the one running this test is locked
+    let target_dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("examples");
+    let manifest = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("../examples")
+        .join(name)
+        .join("Cargo.toml");
+    let status = Command::new(env!("CARGO"))
+        .args(["build", "--release", "--target", WASM_TARGET])
+        .arg("--manifest-path")
+        .arg(manifest)
+        .arg("--target-dir")
+        .arg(&target_dir)
+        .status()
+        .expect("cargo runs");
+    assert!(status.success(), "failed to build the {name} example");
+    Some(
+        target_dir
+            .join(WASM_TARGET)
+            .join("release")
+            .join(format!("{name}.wasm")),
+    )
+}
+
+#[test]
+fn runs_counter_example() {
+    let Some(wasm) = build_example("counter") else {
+        return;
+    };
+    let host = TestHost::new();
+    let deployed = host.deploy_file(wasm).unwrap();
+    let (counter, initialized) = deployed.init::<bool>("init", &[]).unwrap();
+    assert!(initialized);
+    assert_ne!(counter.id(), deployed.id());
+    assert!(matches!(
+        deployed.init::<bool>("init", &[]),
+        Err(Error::Call(CallError::AlreadyInitialized))
+    ));
+
+    let alice = Address::new([1; 32]);
+    let inc = |amount: i64| {
+        counter
+            .call::<Result<(), CounterError>>("inc", &[alice.into(), amount.into()])
+            .unwrap()
+    };
+    assert!(inc(5).is_ok());
+    assert_eq!(counter.call::<i64>("value", &[alice.into()]).unwrap(), 5);
+    // an increment that overflows fails and leaves the count unchanged
+    assert!(matches!(
+        inc(i64::MAX),
+        Err(CounterError::Math(MathError::Overflow))
+    ));
+    assert_eq!(counter.call::<i64>("value", &[alice.into()]).unwrap(), 5);
+}
@@ -0,0 +1,354 @@
+use serde_json::Value as Json;
+use wasmlanche_sdk::errors::CallError;
+use wasmlanche_sdk::event::EmittedEvent;
+use wasmlanche_sdk::host::ABI_VERSION;
+use wasmlanche_sdk::types::Address;
+use wasmlanche_test::{Error, TestHost};
+
+/// A program following the SDK's calling convention, which uses the ids the host gives
+/// the first two programs it deploys. `ABI_VERSION` is replaced by the version of the SDK.
+const PROGRAM: &str = r#"
+(module
+  (import "map" "init_program" (func $init_program (result i64)))
+  (import "map" "store_bytes" (func $store_bytes (param i64 i32 i32 i32 i32) (result i32)))
+  (import "map" "get_bytes_len" (func $get_bytes_len (param i64 i32 i32) (result i32)))
+  (import "map" "get_bytes" (func $get_bytes (param i64 i32 i32 i32) (result i32)))
+  (import "map" "savepoint" (func $savepoint (result i64)))
+  (import "map" "revert" (func $revert (param i64) (result i32)))
+  (import "program" "invoke_program" (func $invoke_program (param i64 i64 i32 i32 i32 i32) (result i64)))
+  (import "program" "invoke_program_with_units" (func $invoke_program_with_units (param i64 i64 i32 i32 i32 i32 i64) (result i64)))
+  (import "program" "set_call_result" (func $set_call_result (param i32 i32) (result i32)))
+  (import "event" "emit_event" (func $emit_event (param i64 i32 i32 i32 i32) (result i32)))
+  (import "context" "actor" (func $actor (param i32) (result i32)))
+  (import "context" "caller" (func $caller (result i64)))
+  (memory (export "memory") 1)
+  (global $heap (mut i32) (i32.const 1024))
+  (data (i32.const 0) "k")
+  ;; the string "v" tagged as a string
+  (data (i32.const 16) "\01v")
+  (data (i32.const 32) "get")
+  ;; the unit value
+  (data (i32.const 48) "\00")
+  (data (i32.const 64) "trap")
+  (data (i32.const 80) "\01trapped")
+  (data (i32.const 96) "spin")
+  (data (i32.const 112) "\01capped")
+  (data (i32.const 128) "Stored")
+  ;; the int tag, followed by the big endian caller once written
+  (data (i32.const 160) "\03")
+  (data (i32.const 176) "caller")
+  ;; an #[init] result, the new program once written followed by the unit value
+  (data (i32.const 192) "\07\00\00\00\00\09\05\00\00\00\00\00\00\00\00\00\00\00\01\00")
+  ;; the address tag, followed by the actor once written
+  (data (i32.const 255) "\04")
+
+  (func (export "abi_version") (result i32)
+    (i32.const ABI_VERSION))
+  (func (export "alloc") (param $len i32) (result i32)
+    global.get $heap
+    (global.set $heap (i32.add (global.get $heap) (local.get $len))))
+  (func $unit
+    (drop (call $set_call_result (i32.const 48) (i32.const 1))))
+
+  (func (export "store_guest") (param i32 i32)
+    (drop (call $store_bytes (i64.const 1) (i32.const 0) (i32.const 1) (i32.const 16) (i32.const 2)))
+    (call $unit))
+  (func (export "get_guest") (param i32 i32)
+    (local $len i32)
+    (local.set $len (call $get_bytes_len (i64.const 1) (i32.const 0) (i32.const 1)))
+    (drop (call $set_call_result
+      (call $get_bytes (i64.const 1) (i32.const 0) (i32.const 1) (local.get $len))
+      (local.get $len))))
+  (func (export "get_from_guest") (param i32 i32)
+    (local $packed i64)
+    (local.set $packed
+      (call $invoke_program (i64.const 2) (i64.const 1) (i32.const 32) (i32.const 3) (i32.const 0) (i32.const 0)))
+    (drop (call $set_call_result
+      (i32.wrap_i64 (i64.shr_u (local.get $packed) (i64.const 32)))
+      (i32.wrap_i64 (local.get $packed)))))
+  (func (export "trap_guest") (param i32 i32)
+    unreachable)
+  (func (export "call_trap_guest") (param i32 i32)
+    (if (i64.eq
+          (call $invoke_program (i64.const 2) (i64.const 1) (i32.const 64) (i32.const 4) (i32.const 0) (i32.const 0))
+          (i64.const -2))
+      (then (drop (call $set_call_result (i32.const 80) (i32.const 8))))))
+  (func (export "store_trap_guest") (param i32 i32)
+    (drop (call $store_bytes (i64.const 1) (i32.const 0) (i32.const 1) (i32.const 16) (i32.const 2)))
+    unreachable)
+  (func (export "store_revert_guest") (param i32 i32)
+    (local $savepoint i64)
+    (local.set $savepoint (call $savepoint))
+    (drop (call $store_bytes (i64.const 1) (i32.const 0) (i32.const 1) (i32.const 16) (i32.const 2)))
+    (drop (call $revert (local.get $savepoint)))
+    (call $unit))
+  (func (export "emit_guest") (param i32 i32)
+    (drop (call $emit_event (i64.const 1) (i32.const 128) (i32.const 6) (i32.const 16) (i32.const 2)))
+    (call $unit))
+  (func (export "emit_as_other_guest") (param i32 i32)
+    ;; claims to be program 2
+    (drop (call $emit_event (i64.const 2) (i32.const 128) (i32.const 6) (i32.const 16) (i32.const 2)))
+    (call $unit))
+  (func (export "emit_revert_guest") (param i32 i32)
+    (local $savepoint i64)
+    (local.set $savepoint (call $savepoint))
+    (drop (call $emit_event (i64.const 1) (i32.const 128) (i32.const 6) (i32.const 16) (i32.const 2)))
+    (drop (call $revert (local.get $savepoint)))
+    (call $unit))
+  (func (export "emit_trap_guest") (param i32 i32)
+    (drop (call $emit_event (i64.const 1) (i32.const 128) (i32.const 6) (i32.const 16) (i32.const 2)))
+    unreachable)
+  (func (export "spin_guest") (param i32 i32)
+    (loop $spin (br $spin)))
+  (func (export "call_spin_guest") (param i32 i32)
+    (if (i64.eq
+          (call $invoke_program_with_units
+            (i64.const 2) (i64.const 1) (i32.const 96) (i32.const 4) (i32.const 0) (i32.const 0) (i64.const 1000))
+          (i64.const -5))
+      (then (drop (call $set_call_result (i32.const 112) (i32.const 7))))))
+  (func (export "actor_guest") (param i32 i32)
+    (drop (call $actor (i32.const 256)))
+    (drop (call $set_call_result (i32.const 255) (i32.const 33))))
+  (func (export "caller_guest") (param i32 i32)
+    (i32.store8 (i32.const 168) (i32.wrap_i64 (call $caller)))
+    (drop (call $set_call_result (i32.const 160) (i32.const 9))))
+  (func (export "spoof_caller_guest") (param i32 i32)
+    (local $packed i64)
+    ;; claims to be program 99
+    (local.set $packed
+      (call $invoke_program (i64.const 99) (i64.const 1) (i32.const 176) (i32.const 6) (i32.const 0) (i32.const 0)))
+    (drop (call $set_call_result
+      (i32.wrap_i64 (i64.shr_u (local.get $packed) (i64.const 32)))
+      (i32.wrap_i64 (local.get $packed)))))
+  (func (export "init_guest") (param i32 i32)
+    ;; the low byte of the new program's big endian id
+    (i32.store8 (i32.const 206) (i32.wrap_i64 (call $init_program)))
+    (drop (call $set_call_result (i32.const 192) (i32.const 20))))
+)
+"#;
+
+fn deploy(host: &TestHost) -> wasmlanche_test::Program {
+    let program = PROGRAM.replace("ABI_VERSION", &ABI_VERSION.to_string());
+    host.deploy(wat::parse_str(program).unwrap()).unwrap()
+}
+
+#[test]
+fn stores_and_invokes() {
+    let host = TestHost::new();
+    let first = deploy(&host);
+    let second = deploy(&host);
+    assert_eq!((first.id(), second.id()), (1, 2));
+
+    first.call::<()>("store", &[]).unwrap();
+    assert_eq!(
+        first.storage_snapshot().into_iter().collect::<Vec<_>>(),
+        vec![(b"k".to_vec(), b"\x01v".to_vec())]
+    );
+    assert!(second.storage_snapshot().is_empty());
+
+    assert_eq!(first.call::<String>("get", &[]).unwrap(), "v");
+    assert_eq!(second.call::<String>("get_from", &[]).unwrap(), "v");
+}
+
+#[test]
+fn reports_failed_calls() {
+    let host = TestHost::new();
+    let first = deploy(&host);
+    let second = deploy(&host);
+
+    assert!(matches!(first.call::<()>("trap", &[]), Err(Error::Trap(_))));
+    assert!(matches!(
+        first.call::<()>("missing", &[]),
+        Err(Error::Call(CallError::UnknownMethod))
+    ));
+    // traps in a called program are returned to the caller
+    assert_eq!(second.call::<String>("call_trap", &[]).unwrap(), "trapped");
+}
+
+#[test]
+fn reverts_changes() {
+    let host = TestHost::new();
+    let program = deploy(&host);
+
+    assert!(matches!(
+        program.call::<()>("store_trap", &[]),
+        Err(Error::Trap(_))
+    ));
+    assert!(program.storage_snapshot().is_empty());
+
+    program.call::<()>("store_revert", &[]).unwrap();
+    assert!(program.storage_snapshot().is_empty());
+}
+
+#[test]
+fn collects_events() {
+    let host = TestHost::new();
+    let program = deploy(&host);
+
+    program.call::<()>("emit", &[]).unwrap();
+    // the events of failed calls are dropped
+    assert!(program.call::<()>("emit_trap", &[]).is_err());
+    // as are those emitted after a savepoint that is reverted to
+    program.call::<()>("emit_revert", &[]).unwrap();
+    assert_eq!(
+        host.events(),
+        vec![EmittedEvent {
+            program_id: 1,
+            topic: "Stored".to_string(),
+            payload: b"\x01v".to_vec(),
+        }]
+    );
+}
+
+#[test]
+fn events_belong_to_the_running_program() {
+    let host = TestHost::new();
+    let program = deploy(&host);
+    let other = deploy(&host);
+    assert_eq!(other.id(), 2);
+
+    program.call::<()>("emit_as_other", &[]).unwrap();
+    assert_eq!(
+        host.events(),
+        vec![EmittedEvent {
+            program_id: 1,
+            topic: "Stored".to_string(),
+            payload: b"\x01v".to_vec(),
+        }]
+    );
+}
+
+#[test]
+fn limits_units() {
+    let host = TestHost::new().with_units(100_000);
+    let first = deploy(&host);
+    let second = deploy(&host);
+
+    assert!(matches!(
+        second.call::<()>("spin", &[]),
+        Err(Error::Call(CallError::InsufficientUnits))
+    ));
+    // a called program runs out of the units it was given before the caller does
+    assert_eq!(first.call::<String>("call_spin", &[]).unwrap(), "capped");
+}
+
+#[test]
+fn passes_actor() {
+    let actor = Address::new([7; 32]);
+    let host = TestHost::new().with_actor(actor);
+    let program = deploy(&host);
+    assert!(program.call::<Address>("actor", &[]).unwrap() == actor);
+}
+
+#[test]
+fn passes_caller() {
+    let host = TestHost::new();
+    let first = deploy(&host);
+    let second = deploy(&host);
+
+    // the actor calls programs directly
+    assert_eq!(first.call::<i64>("caller", &[]).unwrap(), 0);
+    // programs can not claim to be another when invoking one
+    assert_eq!(second.call::<i64>("spoof_caller", &[]).unwrap(), 2);
+}
+
+#[test]
+fn init_creates_program_running_same_code() {
+    let host = TestHost::new();
+    let deployed = deploy(&host);
+    let (created, ()) = deployed.init("init", &[]).unwrap();
+    assert_eq!(created.id(), 2);
+    created.call::<()>("store", &[]).unwrap();
+
+    assert!(matches!(
+        deployed.init::<()>("store", &[]),
+        Err(Error::NoProgramCreated)
+    ));
+}
+
+/// Programs importing every host function of the manifest must link.
+#[test]
+fn defines_abi_manifest() {
+    let manifest = include_str!("../../../abi/abi.json");
+    let manifest: Json = serde_json::from_str(manifest).unwrap();
+
+    let mut imports = String::new();
+    for module in manifest["modules"].as_array().unwrap() {
+        for function in module["functions"].as_array().unwrap() {
+            let wasm_type = |ty: &Json| match ty.as_str().unwrap() {
+                "i32" | "u32" | "ptr" | "len" => "i32",
+                "i64" | "u64" => "i64",
+                other => panic!("unknown type {other}"),
+            };
+            let params: Vec<_> = function["params"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|param| wasm_type(&param["type"]))
+                .collect();
+            let results: Vec<_> = function["results"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(wasm_type)
+                .collect();
+            imports.push_str(&format!(
+                "(import {} {} (func (param {}) (result {})))\n",
+                module["name"],
+                function["name"],
+                params.join(" "),
+                results.join(" "),
+            ));
+        }
+    }
+    let program = format!(
+        r#"(module
+          {imports}
+          (import "program" "set_call_result" (func $set_call_result (param i32 i32) (result i32)))
+          (memory (export "memory") 1)
+          (data (i32.const 0) "\00")
+          (func (export "abi_version") (result i32) i32.const {ABI_VERSION})
+          (func (export "alloc") (param i32) (result i32) i32.const 16)
+          (func (export "link_guest") (param i32 i32)
+            (drop (call $set_call_result (i32.const 0) (i32.const 1)))))"#
+    );
+
+    let program = TestHost::new()
+        .deploy(wat::parse_str(program).unwrap())
+        .unwrap();
+    program.call::<()>("link", &[]).unwrap();
+}
+
+#[test]
+fn rejects_other_abi_versions() {
+    let program = |version: &str| {
+        format!(
+            r#"(module
+              (import "program" "set_call_result" (func $set_call_result (param i32 i32) (result i32)))
+              (memory (export "memory") 1)
+              (data (i32.const 0) "\00")
+              {version}
+              (func (export "alloc") (param i32) (result i32) i32.const 16)
+              (func (export "get_guest") (param i32 i32)
+                (drop (call $set_call_result (i32.const 0) (i32.const 1)))))"#
+        )
+    };
+    let call = |version: &str| {
+        TestHost::new()
+            .deploy(wat::parse_str(program(version)).unwrap())
+            .unwrap()
+            .call::<()>("get", &[])
+    };
+
+    let current = format!(r#"(func (export "abi_version") (result i32) i32.const {ABI_VERSION})"#);
+    call(&current).unwrap();
+    let older = format!(
+        r#"(func (export "abi_version") (result i32) i32.const {})"#,
+        ABI_VERSION - 1
+    );
+    assert!(matches!(
+        call(&older),
+        Err(Error::AbiVersion { found }) if found == Some(ABI_VERSION - 1)
+    ));
+    assert!(matches!(call(""), Err(Error::AbiVersion { found: None })));
+}